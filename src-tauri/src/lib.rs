@@ -2,11 +2,16 @@
 use base64::Engine;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map as JsonMap, Value as JsonValue};
+use sha2::{Digest, Sha256};
+use similar::{ChangeTag, TextDiff};
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use toml::map::Map as TomlMap;
 use toml::Value as TomlValue;
 use url::Url;
@@ -15,6 +20,10 @@ thread_local! {
     static TOKEN_OVERRIDE: RefCell<Option<String>> = RefCell::new(None);
 }
 
+static OFFLINE_MODE: AtomicBool = AtomicBool::new(false);
+static HOME_OVERRIDE: Mutex<Option<PathBuf>> = Mutex::new(None);
+static SESSION_GITHUB_TOKEN: Mutex<Option<String>> = Mutex::new(None);
+
 #[derive(Clone)]
 struct SourceConfig {
     id: &'static str,
@@ -22,6 +31,7 @@ struct SourceConfig {
     install_root: PathBuf,
     root: PathBuf,
     core_files: Vec<&'static str>,
+    flat: bool,
 }
 
 #[derive(Clone, Copy)]
@@ -49,7 +59,9 @@ struct SkillSource {
     id: String,
     label: String,
     root: String,
+    installed: bool,
     exists: bool,
+    core_files: Vec<String>,
     skills: Vec<SkillItem>,
 }
 
@@ -76,6 +88,7 @@ struct SkillTreeNode {
     path: String,
     kind: String,
     children: Vec<SkillTreeNode>,
+    truncated: bool,
 }
 
 #[derive(Serialize)]
@@ -87,6 +100,84 @@ struct McpSource {
     format: String,
     exists: bool,
     servers: Vec<McpServer>,
+    warnings: Vec<String>,
+    read_only: bool,
+    inputs: Vec<JsonValue>,
+    env_file_keys: HashMap<String, Vec<String>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EnvironmentInfo {
+    home: String,
+    skill_sources: Vec<SkillSourcePaths>,
+    mcp_sources: Vec<McpSourcePaths>,
+    token_source: Option<String>,
+    offline: bool,
+    log_path: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SkillSourcePaths {
+    id: String,
+    install_root: String,
+    install_root_exists: bool,
+    root: String,
+    root_exists: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpSourcePaths {
+    id: String,
+    primary_path: String,
+    read_path: String,
+    read_path_exists: bool,
+    read_paths: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpReadPathEntry {
+    path: String,
+    exists: bool,
+    selected: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpReadPaths {
+    primary_path: String,
+    primary_path_exists: bool,
+    selected_path: String,
+    merged: bool,
+    read_paths: Vec<McpReadPathEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SourceSummary {
+    skill_sources: Vec<SkillSourceSummary>,
+    mcp_sources: Vec<McpSourceSummary>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SkillSourceSummary {
+    id: String,
+    label: String,
+    installed: bool,
+    skill_count: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpSourceSummary {
+    id: String,
+    label: String,
+    exists: bool,
+    server_count: usize,
 }
 
 #[derive(Serialize)]
@@ -96,7 +187,55 @@ struct McpServer {
     config: JsonValue,
 }
 
+const ALL_SOURCES: &str = "*";
+const PROJECT_MCP_SOURCE: &str = "project";
+const PROJECT_MCP_FILENAME: &str = ".mcp.json";
 const SKILL_SOURCE_FILENAME: &str = ".skill-source.json";
+const SKILL_IGNORE_FILENAME: &str = ".ananke-ignore";
+const MAX_CORE_FILE_DEPTH: usize = 1;
+const DEFAULT_USER_AGENT: &str = "Ananke/0.1";
+const DEFAULT_GITHUB_API_BASE: &str = "https://api.github.com";
+const DEFAULT_GITHUB_API_VERSION: &str = "2022-11-28";
+const GITHUB_JSON_ACCEPT: &str = "application/vnd.github+json";
+const DEFAULT_BRANCH_ATTEMPTS: u64 = 3;
+const DEFAULT_DOWNLOAD_CONCURRENCY: usize = 4;
+const DESCRIPTION_EXCERPT_CHARS: usize = 160;
+const MAX_SKILL_TREE_DEPTH: usize = 32;
+const REMOTE_SKILLS_CACHE_SECS: u64 = 300;
+const DEFAULT_BRANCH_CACHE_SECS: u64 = 3600;
+const GITHUB_RAW_ACCEPT: &str = "application/vnd.github.raw+json";
+const ANTIGRAVITY_EXTRA_URL_KEY: &str = "antigravityUrl";
+const SSE_MESSAGE_URL_KEY: &str = "messageUrl";
+const SSE_MESSAGE_URL_ALIASES: [&str; 3] = ["postUrl", "message_url", "post_url"];
+const EXPORT_MANIFEST_NAME: &str = "manifest.json";
+const MAX_SKILL_ICON_BYTES: u64 = 512 * 1024;
+const MAX_SKILL_ARCHIVE_BYTES: u64 = 50 * 1024 * 1024;
+const MAX_ARCHIVE_ENTRY_BYTES: u64 = 50 * 1024 * 1024;
+const MAX_ARCHIVE_EXTRACTED_BYTES: u64 = 200 * 1024 * 1024;
+const EXPORT_FORMAT_VERSION: u32 = 1;
+
+#[derive(Deserialize, Serialize)]
+struct SkillSourceRecord {
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    branch: Option<String>,
+    #[serde(rename = "coreHash", default, skip_serializing_if = "Option::is_none")]
+    core_hash: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpSnapshotFile {
+    path: String,
+    existed: bool,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    enabled_sources: Option<Vec<String>>,
+}
 
 #[derive(Clone)]
 struct GithubLocation {
@@ -121,6 +260,195 @@ struct InstallSkillInput {
     source_id: String,
     url: String,
     token: Option<String>,
+    on_existing: Option<String>,
+    /// Install only the core file; the skill tree will then show just that file.
+    #[serde(default)]
+    only_core: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PreviewRemoteSkillInput {
+    url: String,
+    token: Option<String>,
+    source_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PreviewInstallSlugInput {
+    source_id: String,
+    url: String,
+    token: Option<String>,
+    on_existing: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InstallSlugPreview {
+    slug: String,
+    updates_existing: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoteSkillPreview {
+    name: String,
+    description: String,
+    core_file: String,
+    metadata: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListRemoteSkillsInput {
+    url: String,
+    token: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct RemoteSkillEntry {
+    dir: String,
+    url: String,
+    core_file: Option<String>,
+    name: Option<String>,
+    description: Option<String>,
+}
+
+type RemoteSkillsCache = HashMap<String, (u64, Vec<RemoteSkillEntry>)>;
+type SkillIconCache = HashMap<PathBuf, (Option<SystemTime>, u64, SkillIcon)>;
+
+static DEFAULT_BRANCHES: OnceLock<Mutex<HashMap<String, (u64, String)>>> = OnceLock::new();
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InstallToSourcesInput {
+    source_ids: Vec<String>,
+    url: String,
+    token: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SourceInstallResult {
+    skill: Option<SkillItem>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncSkillCoreInput {
+    source_id: String,
+    skill_id: String,
+    token: Option<String>,
+    #[serde(default)]
+    force: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResetSkillInput {
+    source_id: String,
+    skill_id: String,
+    token: Option<String>,
+    #[serde(default)]
+    confirm: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncSkillsAllInput {
+    source_id: String,
+    #[serde(default)]
+    skill_ids: Vec<String>,
+    pattern: Option<String>,
+    #[serde(default)]
+    incremental: bool,
+    #[serde(default)]
+    mirror: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SourceSyncResult {
+    result: Option<SyncResult>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InstallResult {
+    #[serde(flatten)]
+    skill: SkillItem,
+    created: bool,
+    files_downloaded: usize,
+    bytes_downloaded: u64,
+}
+
+#[derive(Default)]
+struct DownloadStats {
+    files: usize,
+    bytes: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GithubBranchesInput {
+    url: String,
+    token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GithubTokenInput {
+    token: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GithubRateLimit {
+    limit: u64,
+    remaining: u64,
+    reset: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SkillCommitInput {
+    source_id: String,
+    skill_id: String,
+    token: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SkillCommit {
+    sha: String,
+    date: Option<String>,
+    message: String,
+    author: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SkillDescriptionInput {
+    source_id: String,
+    skill_id: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SkillDescription {
+    description: String,
+    source: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GithubBranch {
+    name: String,
+    is_default: bool,
 }
 
 #[derive(Deserialize)]
@@ -130,6 +458,13 @@ struct DeleteSkillInput {
     skill_id: String,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteSkillsInput {
+    source_id: String,
+    skill_ids: Vec<String>,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct SkillTreeInput {
@@ -144,87 +479,797 @@ struct SyncSkillInput {
     skill_id: String,
     url: String,
     token: Option<String>,
+    branch: Option<String>,
+    #[serde(default)]
+    force: bool,
+    #[serde(default)]
+    recreate: bool,
 }
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct UpsertMcpJsonInput {
+struct SkillSourceInput {
     source_id: String,
-    json: String,
 }
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct DeleteMcpInput {
+struct ListSkillsInput {
+    sort_by: Option<String>,
+    #[serde(default)]
+    descending: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FindSkillInput {
+    query: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SkillLocation {
     source_id: String,
-    id: String,
+    skill_id: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RelinkCandidate {
+    skill_id: String,
+    from_source_id: String,
+    from_skill_id: String,
+    url: String,
 }
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct SyncAgentsInput {
+struct SyncAllSkillsInput {
     source_id: String,
-    target_id: String,
+    token: Option<String>,
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
-struct SyncResult {
-    added: usize,
-    skipped: usize,
+struct SkillSyncReport {
+    skill_id: String,
+    status: String,
+    error: Option<String>,
 }
 
-fn resolve_home() -> Result<PathBuf, String> {
-    if let Ok(home) = std::env::var("HOME") {
-        return Ok(PathBuf::from(home));
-    }
-    dirs::home_dir().ok_or_else(|| "Unable to resolve home directory".to_string())
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DiffSkillInput {
+    source_id: String,
+    skill_id: String,
+    token: Option<String>,
 }
 
-fn source_configs(home: &Path) -> Vec<SourceConfig> {
-    let skill_md = vec!["SKILL.md"];
-    let antigravity_files = vec!["manifest.json", "SKILL.md"];
-    let codebuddy_files = vec![".cb-rules", "SKILL.md"];
-    let kiro_files = vec!["instructions.md"];
-    let qoder_files = vec!["config.yaml"];
-    let antigravity_root = {
-        let gemini_root = home.join(".gemini").join("antigravity");
-        let legacy_root = home.join(".antigravity");
-        if gemini_root.exists() || !legacy_root.exists() {
-            gemini_root
-        } else {
-            legacy_root
-        }
-    };
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DiffSkillUrlInput {
+    source_id: String,
+    skill_id: String,
+    url: String,
+    token: Option<String>,
+}
 
-    vec![
-        SourceConfig {
-            id: "claude-user",
-            label: "Claude Code",
-            install_root: home.join(".claude"),
-            root: home.join(".claude").join("skills"),
-            core_files: skill_md.clone(),
-        },
-        SourceConfig {
-            id: "roo-user",
-            label: "Roo Code (Cline)",
-            install_root: home.join(".roo"),
-            root: home.join(".roo").join("skills"),
-            core_files: skill_md.clone(),
-        },
-        SourceConfig {
-            id: "copilot-user",
-            label: "GitHub Copilot",
-            install_root: home.join(".copilot"),
-            root: home.join(".copilot").join("skills"),
-            core_files: skill_md.clone(),
-        },
-        SourceConfig {
-            id: "cursor-user",
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiffLine {
+    tag: String,
+    content: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DiffSkillTreesInput {
+    a_source_id: String,
+    a_skill_id: String,
+    b_source_id: String,
+    b_skill_id: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReslugResult {
+    renamed: HashMap<String, String>,
+    failed: HashMap<String, String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SkillIcon {
+    mime_type: String,
+    data: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SkillTreeDiff {
+    identical: bool,
+    added: Vec<String>,
+    removed: Vec<String>,
+    changed: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SkillDiff {
+    core_file: String,
+    changed: bool,
+    lines: Vec<DiffLine>,
+    added_files: Vec<String>,
+    removed_files: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PruneSkillSourcesInput {
+    source_id: String,
+    #[serde(default)]
+    clear: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetSkillSourceUrlInput {
+    source_id: String,
+    skill_id: String,
+    url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetSkillMetadataInput {
+    source_id: String,
+    skill_id: String,
+    key: String,
+    value: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BulkSetMetadataInput {
+    source_id: String,
+    skill_ids: Option<Vec<String>>,
+    key: String,
+    value: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpsertMcpJsonInput {
+    source_id: String,
+    json: String,
+    target_path: Option<String>,
+    project_root: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ValidateMcpJsonInput {
+    json: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpValidation {
+    valid: bool,
+    servers: Vec<String>,
+    errors: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PreviewMcpUpsertInput {
+    source_id: String,
+    json: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteMcpInput {
+    source_id: String,
+    id: String,
+    target_path: Option<String>,
+    project_root: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestoreMcpSnapshotInput {
+    source_id: String,
+    snapshot_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MergeDuplicateMcpInput {
+    source_id: String,
+    keep: String,
+    remove: Vec<String>,
+    target_path: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpDuplicateGroup {
+    fingerprint: String,
+    ids: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DiagnoseAllInput {
+    #[serde(default)]
+    check_urls: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiagnosticIssue {
+    source_id: String,
+    subject: String,
+    message: String,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct DiagnosticReport {
+    config_errors: Vec<DiagnosticIssue>,
+    missing_core_files: Vec<DiagnosticIssue>,
+    unreachable_urls: Vec<DiagnosticIssue>,
+    duplicate_mcp_servers: Vec<DiagnosticIssue>,
+    missing_commands: Vec<DiagnosticIssue>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportMcpServerInput {
+    source_id: String,
+    id: String,
+    #[serde(default)]
+    reveal: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MigrateMcpInput {
+    source_id: String,
+    from_path: String,
+    to_path: String,
+    ids: Vec<String>,
+    #[serde(default)]
+    remove_from_source: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetAllMcpEnabledInput {
+    source_id: String,
+    enabled: bool,
+    target_path: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpToggleResult {
+    changed: usize,
+    unchanged: usize,
+    backup_path: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MergeMcpServerInput {
+    a_source_id: String,
+    b_source_id: String,
+    id: String,
+    target_source_id: String,
+    prefer: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncAgentsInput {
+    source_id: String,
+    target_id: String,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncSkillsAgentsInput {
+    source_id: String,
+    target_id: String,
+    #[serde(default)]
+    skill_ids: Vec<String>,
+    pattern: Option<String>,
+    #[serde(default)]
+    incremental: bool,
+    #[serde(default)]
+    mirror: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OfflineModeInput {
+    enabled: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EnabledSourcesInput {
+    source_ids: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HomeOverrideInput {
+    path: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MigrateAntigravityInput {
+    #[serde(default)]
+    remove_legacy: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AntigravityMigration {
+    skills_copied: Vec<String>,
+    skills_skipped: Vec<String>,
+    servers_added: usize,
+    servers_skipped: usize,
+    legacy_backup_path: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportAllInput {
+    dest_path: String,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportManifest {
+    format_version: u32,
+    app_version: String,
+    exported_at: u64,
+    skill_sources: Vec<ExportedSource>,
+    mcp_sources: Vec<ExportedSource>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedSource {
+    id: String,
+    items: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportAllInput {
+    archive_path: String,
+    on_conflict: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportItemReport {
+    kind: String,
+    source_id: String,
+    item_id: String,
+    status: String,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportSummary {
+    path: String,
+    skills: usize,
+    mcp_servers: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct McpSourceInput {
+    source_id: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryEntry {
+    timestamp: u64,
+    action: String,
+    source_id: String,
+    target: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    outcome: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryInput {
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListMcpSourcesInput {
+    project_root: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct McpPathInput {
+    path: String,
+    kind: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpsertMcpPathInput {
+    path: String,
+    kind: String,
+    json: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct McpTransportInput {
+    transport: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpServerGroup {
+    source_id: String,
+    label: String,
+    servers: Vec<McpServer>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpHealthIssue {
+    server_id: String,
+    level: String,
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestMcpServerInput {
+    source_id: String,
+    id: String,
+    #[serde(default)]
+    inputs: HashMap<String, String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpTestResult {
+    ok: bool,
+    message: String,
+    missing_inputs: Vec<JsonValue>,
+    server_info: Option<JsonValue>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpSchemaField {
+    name: String,
+    value_type: String,
+    description: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    allowed_values: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpSchema {
+    format: String,
+    fields: Vec<McpSchemaField>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct McpCommandCheck {
+    server_id: String,
+    command: String,
+    found: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NormalizedMcpFile {
+    path: String,
+    backup_path: Option<String>,
+    servers: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncResult {
+    added: usize,
+    skipped: usize,
+    updated: usize,
+    added_ids: Vec<String>,
+    skipped_ids: Vec<String>,
+    updated_ids: Vec<String>,
+}
+
+#[derive(Default)]
+struct CopyStats {
+    copied: usize,
+    removed: usize,
+}
+
+fn resolve_home() -> Result<PathBuf, String> {
+    if let Some(home) = HOME_OVERRIDE.lock().ok().and_then(|guard| guard.clone()) {
+        return Ok(home);
+    }
+    let home = match std::env::var("HOME") {
+        Ok(home) => PathBuf::from(home),
+        Err(_) => dirs::home_dir().ok_or_else(|| "Unable to resolve home directory".to_string())?,
+    };
+    log::debug!("resolved home {}", home.display());
+    Ok(home)
+}
+
+fn ananke_dir(home: &Path) -> PathBuf {
+    home.join(".ananke")
+}
+
+fn log_file_path(home: &Path) -> PathBuf {
+    ananke_dir(home).join("ananke.log")
+}
+
+fn mcp_snapshots_dir(home: &Path, source_id: &str) -> PathBuf {
+    ananke_dir(home).join("mcp-snapshots").join(source_id)
+}
+
+fn settings_path(home: &Path) -> PathBuf {
+    ananke_dir(home).join("settings.json")
+}
+
+fn history_path(home: &Path) -> PathBuf {
+    ananke_dir(home).join("history.jsonl")
+}
+
+fn history_url(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url.trim()) else {
+        return url
+            .trim()
+            .split(['?', '#'])
+            .next()
+            .unwrap_or_default()
+            .to_string();
+    };
+    let _ = parsed.set_username("");
+    let _ = parsed.set_password(None);
+    parsed.set_query(None);
+    parsed.set_fragment(None);
+    parsed.to_string()
+}
+
+fn history_error(error: &str) -> String {
+    let mut sanitized = String::new();
+    let mut rest = error;
+    while let Some(start) = ["http://", "https://"]
+        .iter()
+        .filter_map(|scheme| rest.find(scheme))
+        .min()
+    {
+        sanitized.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        let end = tail
+            .find(|ch: char| ch.is_whitespace() || matches!(ch, '"' | '\'' | '<' | '>' | ')'))
+            .unwrap_or(tail.len());
+        let url = tail[..end].trim_end_matches([',', ';', ':', '.']);
+        sanitized.push_str(&history_url(url));
+        rest = &tail[url.len()..];
+    }
+    sanitized.push_str(rest);
+    sanitized
+}
+
+fn record_history<T>(
+    action: &str,
+    source_id: &str,
+    target: &str,
+    url: Option<&str>,
+    result: &Result<T, String>,
+) {
+    let Ok(home) = resolve_home() else {
+        return;
+    };
+    let entry = HistoryEntry {
+        timestamp: unix_timestamp(),
+        action: action.to_string(),
+        source_id: source_id.to_string(),
+        target: target.to_string(),
+        url: url.map(history_url),
+        outcome: if result.is_ok() { "ok" } else { "error" }.to_string(),
+        error: result.as_ref().err().map(|err| history_error(err)),
+    };
+    let Ok(line) = serde_json::to_string(&entry) else {
+        return;
+    };
+    let path = history_path(&home);
+    let written = fs::create_dir_all(ananke_dir(&home)).and_then(|_| {
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(format!("{}\n", line).as_bytes()))
+    });
+    if let Err(err) = written {
+        log::warn!("failed to record history in {}: {}", path.display(), err);
+    }
+}
+
+fn read_app_settings(home: &Path) -> AppSettings {
+    fs::read_to_string(settings_path(home))
+        .ok()
+        .and_then(|content| serde_json::from_str(strip_bom(&content)).ok())
+        .unwrap_or_default()
+}
+
+fn write_app_settings(home: &Path, settings: &AppSettings) -> Result<(), String> {
+    let path = settings_path(home);
+    fs::create_dir_all(ananke_dir(home))
+        .map_err(|err| format!("Failed to create {}: {}", ananke_dir(home).display(), err))?;
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|err| format!("Failed to serialize JSON: {}", err))?;
+    fs::write(&path, format!("{}\n", content)).map_err(|err| write_error("Config file", &path, err))
+}
+
+fn source_enabled(settings: &AppSettings, id: &str) -> bool {
+    settings
+        .enabled_sources
+        .as_ref()
+        .is_none_or(|ids| ids.iter().any(|item| item == id))
+}
+
+struct FileLogger {
+    level: log::LevelFilter,
+    file: Mutex<fs::File>,
+}
+
+impl log::Log for FileLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.level && metadata.target().starts_with(module_path!())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{} {} {}\n",
+            unix_timestamp(),
+            record.level(),
+            record.args()
+        );
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+fn log_level_from_env() -> Option<log::LevelFilter> {
+    let value = std::env::var("ANANKE_LOG")
+        .or_else(|_| std::env::var("RUST_LOG"))
+        .ok()?;
+    value
+        .split(',')
+        .filter_map(|directive| {
+            let level = directive.rsplit('=').next().unwrap_or(directive);
+            level.trim().parse::<log::LevelFilter>().ok()
+        })
+        .max()
+        .filter(|level| *level != log::LevelFilter::Off)
+}
+
+fn init_logging() {
+    let Some(level) = log_level_from_env() else {
+        return;
+    };
+    let Ok(home) = resolve_home() else {
+        return;
+    };
+    let path = log_file_path(&home);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let Ok(file) = fs::OpenOptions::new().create(true).append(true).open(&path) else {
+        return;
+    };
+    let logger = FileLogger {
+        level,
+        file: Mutex::new(file),
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+fn extra_core_files() -> &'static [&'static str] {
+    static EXTRA_CORE_FILES: OnceLock<Vec<&'static str>> = OnceLock::new();
+    EXTRA_CORE_FILES.get_or_init(|| {
+        std::env::var("ANANKE_EXTRA_CORE_FILES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && !name.contains(['/', '\\']))
+            .map(|name| &*Box::leak(name.to_string().into_boxed_str()))
+            .collect()
+    })
+}
+
+fn source_configs(home: &Path) -> Vec<SourceConfig> {
+    let skill_md = vec!["SKILL.md"];
+    let antigravity_files = vec!["manifest.json", "SKILL.md"];
+    let codebuddy_files = vec![".cb-rules", "SKILL.md"];
+    let kiro_files = vec!["instructions.md"];
+    let qoder_files = vec!["config.yaml"];
+    let antigravity_root = {
+        let gemini_root = home.join(".gemini").join("antigravity");
+        let legacy_root = home.join(".antigravity");
+        if gemini_root.exists() || !legacy_root.exists() {
+            gemini_root
+        } else {
+            legacy_root
+        }
+    };
+
+    let mut sources = vec![
+        SourceConfig {
+            id: "claude-user",
+            label: "Claude Code",
+            install_root: home.join(".claude"),
+            root: home.join(".claude").join("skills"),
+            core_files: skill_md.clone(),
+            flat: false,
+        },
+        SourceConfig {
+            id: "roo-user",
+            label: "Roo Code (Cline)",
+            install_root: home.join(".roo"),
+            root: home.join(".roo").join("skills"),
+            core_files: skill_md.clone(),
+            flat: false,
+        },
+        SourceConfig {
+            id: "copilot-user",
+            label: "GitHub Copilot",
+            install_root: home.join(".copilot"),
+            root: home.join(".copilot").join("skills"),
+            core_files: skill_md.clone(),
+            flat: false,
+        },
+        SourceConfig {
+            id: "cursor-user",
             label: "Cursor",
             install_root: home.join(".cursor"),
             root: home.join(".cursor").join("skills"),
             core_files: skill_md.clone(),
+            flat: false,
         },
         SourceConfig {
             id: "opencode-user",
@@ -232,6 +1277,7 @@ fn source_configs(home: &Path) -> Vec<SourceConfig> {
             install_root: home.join(".config").join("opencode"),
             root: home.join(".config").join("opencode").join("skills"),
             core_files: skill_md.clone(),
+            flat: false,
         },
         SourceConfig {
             id: "gemini-user",
@@ -239,6 +1285,7 @@ fn source_configs(home: &Path) -> Vec<SourceConfig> {
             install_root: home.join(".gemini"),
             root: home.join(".gemini").join("skills"),
             core_files: skill_md.clone(),
+            flat: false,
         },
         SourceConfig {
             id: "codex-user",
@@ -246,6 +1293,7 @@ fn source_configs(home: &Path) -> Vec<SourceConfig> {
             install_root: home.join(".codex"),
             root: home.join(".codex").join("skills"),
             core_files: skill_md.clone(),
+            flat: false,
         },
         SourceConfig {
             id: "trae-user",
@@ -253,6 +1301,7 @@ fn source_configs(home: &Path) -> Vec<SourceConfig> {
             install_root: home.join(".trae"),
             root: home.join(".trae").join("skills"),
             core_files: skill_md.clone(),
+            flat: false,
         },
         SourceConfig {
             id: "goose-user",
@@ -260,6 +1309,7 @@ fn source_configs(home: &Path) -> Vec<SourceConfig> {
             install_root: home.join(".config").join("goose"),
             root: home.join(".config").join("goose").join("skills"),
             core_files: skill_md.clone(),
+            flat: false,
         },
         SourceConfig {
             id: "standard-user",
@@ -267,6 +1317,7 @@ fn source_configs(home: &Path) -> Vec<SourceConfig> {
             install_root: home.join(".skills"),
             root: home.join(".skills"),
             core_files: skill_md.clone(),
+            flat: true,
         },
         SourceConfig {
             id: "antigravity-user",
@@ -274,1899 +1325,8392 @@ fn source_configs(home: &Path) -> Vec<SourceConfig> {
             install_root: antigravity_root.clone(),
             root: antigravity_root.join("skills"),
             core_files: antigravity_files.clone(),
+            flat: false,
+        },
+        SourceConfig {
+            id: "kiro-user",
+            label: "Kiro",
+            install_root: home.join(".kiro"),
+            root: home.join(".kiro").join("skills"),
+            core_files: kiro_files.clone(),
+            flat: false,
+        },
+        SourceConfig {
+            id: "qoder-user",
+            label: "Qoder",
+            install_root: home.join(".qoder"),
+            root: home.join(".qoder").join("skills"),
+            core_files: qoder_files.clone(),
+            flat: false,
+        },
+        SourceConfig {
+            id: "codebuddy-user",
+            label: "CodeBuddy",
+            install_root: home.join(".codebuddy"),
+            root: home.join(".codebuddy").join("skills"),
+            core_files: codebuddy_files.clone(),
+            flat: false,
+        },
+    ];
+
+    for source in &mut sources {
+        for name in extra_core_files() {
+            if !source.core_files.contains(name) {
+                source.core_files.push(name);
+            }
+        }
+        if let Some(root) = configured_skills_root(home, source.id, &source.install_root) {
+            source.root = root;
+        }
+    }
+    sources
+}
+
+fn configured_skills_root(home: &Path, source_id: &str, install_root: &Path) -> Option<PathBuf> {
+    let (config_file, pointer) = match source_id {
+        "opencode-user" => ("opencode.json", "/skills/paths"),
+        _ => return None,
+    };
+    let value = load_json_value(&install_root.join(config_file)).ok()?;
+    let configured = match value.pointer(pointer)? {
+        JsonValue::String(path) => Some(path.as_str()),
+        JsonValue::Array(items) => items.iter().find_map(|item| item.as_str()),
+        _ => None,
+    }?
+    .trim();
+    if configured.is_empty() {
+        return None;
+    }
+    let root = if let Some(rest) = configured.strip_prefix("~/") {
+        home.join(rest)
+    } else {
+        install_root.join(configured)
+    };
+    log::debug!(
+        "{} skills root from {}: {}",
+        source_id,
+        config_file,
+        root.display()
+    );
+    Some(root)
+}
+
+fn mcp_source_configs(home: &Path) -> Vec<McpSourceConfig> {
+    let claude_primary = home.join(".claude.json");
+    let claude_alt = home.join(".claude").join(".mcp.json");
+    let claude_legacy = home.join(".claude").join("mcp.json");
+    let codex_path = home.join(".codex").join("config.toml");
+    let opencode_path = home.join(".config").join("opencode").join("opencode.json");
+    let roo_path = home.join(".roo").join("mcp.json");
+    let copilot_path = home.join(".copilot").join("mcp.json");
+    let cursor_path = home.join(".cursor").join("mcp.json");
+    let gemini_path = home.join(".gemini").join("settings.json");
+    let gemini_legacy = home.join(".gemini").join("mcp.json");
+    let trae_path = home.join(".trae").join("mcp.json");
+    let goose_path = home.join(".config").join("goose").join("mcp.json");
+    let antigravity_primary = home
+        .join(".gemini")
+        .join("antigravity")
+        .join("mcp_config.json");
+    let antigravity_legacy = home.join(".antigravity").join("mcp.json");
+    let antigravity_path = if antigravity_primary.exists() || !antigravity_legacy.exists() {
+        antigravity_primary.clone()
+    } else {
+        antigravity_legacy.clone()
+    };
+    let antigravity_root = antigravity_path
+        .parent()
+        .map(|parent| parent.to_path_buf())
+        .unwrap_or_else(|| home.join(".gemini").join("antigravity"));
+    let kiro_path = home.join(".kiro").join("mcp.json");
+    let qoder_path = home.join(".qoder").join("mcp.json");
+    let codebuddy_path = home.join(".codebuddy").join("mcp.json");
+
+    vec![
+        McpSourceConfig {
+            id: "claude",
+            label: "Claude Code",
+            format: "json",
+            kind: McpKind::ClaudeJson,
+            install_root: home.join(".claude"),
+            primary_path: claude_primary.clone(),
+            read_paths: vec![claude_primary, claude_alt, claude_legacy],
+        },
+        McpSourceConfig {
+            id: "roo",
+            label: "Roo Code (Cline)",
+            format: "json",
+            kind: McpKind::ClaudeJson,
+            install_root: home.join(".roo"),
+            primary_path: roo_path.clone(),
+            read_paths: vec![roo_path],
+        },
+        McpSourceConfig {
+            id: "copilot",
+            label: "GitHub Copilot",
+            format: "json",
+            kind: McpKind::ClaudeJson,
+            install_root: home.join(".copilot"),
+            primary_path: copilot_path.clone(),
+            read_paths: vec![copilot_path],
+        },
+        McpSourceConfig {
+            id: "cursor",
+            label: "Cursor",
+            format: "json",
+            kind: McpKind::ClaudeJson,
+            install_root: home.join(".cursor"),
+            primary_path: cursor_path.clone(),
+            read_paths: vec![cursor_path],
+        },
+        McpSourceConfig {
+            id: "gemini",
+            label: "Gemini CLI",
+            format: "json",
+            kind: McpKind::ClaudeJson,
+            install_root: home.join(".gemini"),
+            primary_path: gemini_path.clone(),
+            read_paths: vec![gemini_path, gemini_legacy],
+        },
+        McpSourceConfig {
+            id: "codex",
+            label: "Codex",
+            format: "toml",
+            kind: McpKind::CodexToml,
+            install_root: home.join(".codex"),
+            primary_path: codex_path.clone(),
+            read_paths: vec![codex_path],
+        },
+        McpSourceConfig {
+            id: "opencode",
+            label: "OpenCode",
+            format: "json",
+            kind: McpKind::OpenCodeJson,
+            install_root: home.join(".config").join("opencode"),
+            primary_path: opencode_path.clone(),
+            read_paths: vec![opencode_path],
+        },
+        McpSourceConfig {
+            id: "trae",
+            label: "Trae",
+            format: "json",
+            kind: McpKind::ClaudeJson,
+            install_root: home.join(".trae"),
+            primary_path: trae_path.clone(),
+            read_paths: vec![trae_path],
+        },
+        McpSourceConfig {
+            id: "goose",
+            label: "Goose",
+            format: "json",
+            kind: McpKind::ClaudeJson,
+            install_root: home.join(".config").join("goose"),
+            primary_path: goose_path.clone(),
+            read_paths: vec![goose_path],
+        },
+        McpSourceConfig {
+            id: "antigravity",
+            label: "Antigravity",
+            format: "json",
+            kind: McpKind::AntigravityJson,
+            install_root: antigravity_root,
+            primary_path: antigravity_path.clone(),
+            read_paths: vec![antigravity_primary, antigravity_legacy],
+        },
+        McpSourceConfig {
+            id: "kiro",
+            label: "Kiro",
+            format: "json",
+            kind: McpKind::ClaudeJson,
+            install_root: home.join(".kiro"),
+            primary_path: kiro_path.clone(),
+            read_paths: vec![kiro_path],
+        },
+        McpSourceConfig {
+            id: "qoder",
+            label: "Qoder",
+            format: "json",
+            kind: McpKind::ClaudeJson,
+            install_root: home.join(".qoder"),
+            primary_path: qoder_path.clone(),
+            read_paths: vec![qoder_path],
+        },
+        McpSourceConfig {
+            id: "codebuddy",
+            label: "CodeBuddy",
+            format: "json",
+            kind: McpKind::ClaudeJson,
+            install_root: home.join(".codebuddy"),
+            primary_path: codebuddy_path.clone(),
+            read_paths: vec![codebuddy_path],
+        },
+    ]
+}
+
+fn resolve_read_path(config: &McpSourceConfig) -> PathBuf {
+    let path = config
+        .read_paths
+        .iter()
+        .find(|path| path.exists())
+        .unwrap_or(&config.primary_path)
+        .clone();
+    log::debug!("resolved {} MCP read path {}", config.id, path.display());
+    path
+}
+
+fn env_flag(name: &str) -> bool {
+    std::env::var(name)
+        .map(|value| {
+            matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+        .unwrap_or(false)
+}
+
+fn merges_read_paths(config: &McpSourceConfig) -> bool {
+    config.read_paths.len() > 1 && env_flag("ANANKE_MERGE_MCP_READ_PATHS")
+}
+
+fn read_merged_mcp_servers(config: &McpSourceConfig) -> Result<Vec<McpServer>, String> {
+    let mut paths = vec![config.primary_path.clone()];
+    for path in &config.read_paths {
+        if !paths.contains(path) {
+            paths.push(path.clone());
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut servers = Vec::new();
+    for path in paths {
+        for server in read_mcp_servers(config, &path)? {
+            if seen.insert(server.id.clone()) {
+                servers.push(server);
+            }
+        }
+    }
+    servers.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(servers)
+}
+
+fn read_source_mcp_servers(config: &McpSourceConfig) -> Result<Vec<McpServer>, String> {
+    if merges_read_paths(config) {
+        return read_merged_mcp_servers(config);
+    }
+    read_mcp_servers(config, &resolve_read_path(config))
+}
+
+fn mcp_write_config(
+    config: &McpSourceConfig,
+    target_path: Option<&str>,
+) -> Result<McpSourceConfig, String> {
+    match target_path.map(str::trim).filter(|path| !path.is_empty()) {
+        Some(target) => {
+            let target = PathBuf::from(target);
+            if target != config.primary_path && !config.read_paths.contains(&target) {
+                return Err("Target path is not a config file for this MCP source".to_string());
+            }
+            let mut write_config = config.clone();
+            write_config.primary_path = target;
+            Ok(write_config)
+        }
+        None if merges_read_paths(config) => {
+            Err("Merged MCP view is read-only; choose a target file to write to".to_string())
+        }
+        None => Ok(config.clone()),
+    }
+}
+
+fn read_path_warnings(
+    config: &McpSourceConfig,
+    selected: &Path,
+    servers: &[McpServer],
+) -> Vec<String> {
+    let selected_ids: HashSet<&str> = servers.iter().map(|server| server.id.as_str()).collect();
+    let mut warnings = Vec::new();
+
+    for path in &config.read_paths {
+        if path == selected || !path.exists() {
+            continue;
+        }
+        let other = match read_mcp_servers(config, path) {
+            Ok(other) => other,
+            Err(err) => {
+                warnings.push(format!("Ignored {}: {}", path.display(), err));
+                continue;
+            }
+        };
+        let other_ids: HashSet<&str> = other.iter().map(|server| server.id.as_str()).collect();
+        if other_ids == selected_ids {
+            continue;
+        }
+        let mut hidden: Vec<&str> = other_ids.difference(&selected_ids).copied().collect();
+        hidden.sort();
+        if hidden.is_empty() {
+            warnings.push(format!(
+                "{} is ignored and defines a different set of servers than {}",
+                path.display(),
+                selected.display()
+            ));
+        } else {
+            warnings.push(format!(
+                "Servers in {} are not shown or edited ({} is used): {}",
+                path.display(),
+                selected.display(),
+                hidden.join(", ")
+            ));
+        }
+    }
+
+    warnings
+}
+
+fn parse_frontmatter(raw: &str) -> (HashMap<String, String>, String) {
+    let mut metadata = HashMap::new();
+    let mut lines = raw.lines();
+
+    if !matches!(lines.next(), Some(line) if line.trim() == "---") {
+        return (metadata, raw.to_string());
+    }
+
+    let mut frontmatter = Vec::new();
+    let mut found_close = false;
+
+    for line in lines.by_ref() {
+        if line.trim() == "---" {
+            found_close = true;
+            break;
+        }
+        frontmatter.push(line);
+    }
+
+    if !found_close {
+        return (HashMap::new(), raw.to_string());
+    }
+
+    for line in frontmatter {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            let key = key.trim();
+            if key.is_empty() {
+                continue;
+            }
+            metadata.insert(key.to_string(), value.trim().to_string());
+        }
+    }
+
+    let body = lines.collect::<Vec<_>>().join("\n");
+    (metadata, body.trim_start().to_string())
+}
+
+fn set_frontmatter_value(raw: &str, key: &str, value: Option<&str>) -> String {
+    let newline = if raw.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines: Vec<String> = raw.lines().map(|line| line.to_string()).collect();
+    let close = match lines.first() {
+        Some(first) if first.trim() == "---" => lines
+            .iter()
+            .skip(1)
+            .position(|line| line.trim() == "---")
+            .map(|index| index + 1),
+        _ => None,
+    };
+
+    let Some(close) = close else {
+        let Some(value) = value else {
+            return raw.to_string();
+        };
+        return format!("---{nl}{}: {}{nl}---{nl}{}", key, value, raw, nl = newline);
+    };
+
+    let existing = (1..close).find(|index| {
+        let line = &lines[*index];
+        !line.starts_with([' ', '\t', '#'])
+            && line
+                .split_once(':')
+                .is_some_and(|(name, _)| name.trim() == key)
+    });
+    match (existing, value) {
+        (Some(index), Some(value)) => lines[index] = format!("{}: {}", key, value),
+        (Some(index), None) => {
+            lines.remove(index);
+        }
+        (None, Some(value)) => lines.insert(close, format!("{}: {}", key, value)),
+        (None, None) => return raw.to_string(),
+    }
+
+    let mut output = lines.join(newline);
+    if raw.ends_with('\n') {
+        output.push_str(newline);
+    }
+    output
+}
+
+fn extract_description(body: &str) -> String {
+    for line in body.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        return trimmed.to_string();
+    }
+    String::new()
+}
+
+fn description_excerpt(body: &str) -> String {
+    let text = body
+        .lines()
+        .map(|line| line.trim().trim_start_matches('#').trim())
+        .filter(|line| !line.is_empty() && !line.starts_with("```"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if text.chars().count() <= DESCRIPTION_EXCERPT_CHARS {
+        return text;
+    }
+    let mut excerpt: String = text.chars().take(DESCRIPTION_EXCERPT_CHARS).collect();
+    excerpt = excerpt.trim_end().to_string();
+    excerpt.push('…');
+    excerpt
+}
+
+fn skill_description(metadata: &HashMap<String, String>, body: &str) -> (String, &'static str) {
+    if let Some(description) = metadata
+        .get("description")
+        .filter(|value| !value.trim().is_empty())
+    {
+        return (description.clone(), "frontmatter");
+    }
+    let paragraph = extract_description(body);
+    if !paragraph.is_empty() {
+        return (paragraph, "paragraph");
+    }
+    let excerpt = description_excerpt(body);
+    if !excerpt.is_empty() {
+        return (excerpt, "excerpt");
+    }
+    (String::new(), "none")
+}
+
+fn ensure_core_link_within_root(
+    core_file_path: &Path,
+    source: &SourceConfig,
+) -> Result<(), String> {
+    let is_link = fs::symlink_metadata(core_file_path)
+        .map(|metadata| metadata.file_type().is_symlink())
+        .unwrap_or(false);
+    if !is_link {
+        return Ok(());
+    }
+    let root =
+        fs::canonicalize(&source.root).map_err(|err| format!("Failed to resolve root: {}", err))?;
+    let target = fs::canonicalize(core_file_path).map_err(|err| {
+        format!(
+            "Broken core file link {}: {}",
+            core_file_path.display(),
+            err
+        )
+    })?;
+    if !target.starts_with(&root) {
+        return Err(format!(
+            "Core file {} links outside the skills root",
+            core_file_path.display()
+        ));
+    }
+    Ok(())
+}
+
+fn load_skill(
+    skill_dir: &Path,
+    core_file_path: &Path,
+    core_file_name: &str,
+    source: &SourceConfig,
+) -> Result<SkillItem, String> {
+    ensure_core_link_within_root(core_file_path, source)?;
+    let raw = fs::read_to_string(core_file_path)
+        .map_err(|err| format!("Failed to read {}: {}", core_file_path.display(), err))?;
+    let is_markdown = core_file_name.to_ascii_lowercase().ends_with(".md");
+    let (metadata, body) = if is_markdown {
+        parse_frontmatter(&raw)
+    } else {
+        (HashMap::new(), raw.clone())
+    };
+    let source_url = read_skill_source_url(skill_dir);
+    let dir_name = skill_dir
+        .file_name()
+        .and_then(|value| value.to_str())
+        .unwrap_or("skill");
+    let fallback_name = if skill_dir.is_file() {
+        skill_dir
+            .file_stem()
+            .and_then(|value| value.to_str())
+            .unwrap_or(dir_name)
+    } else {
+        dir_name
+    };
+
+    let name = metadata
+        .get("name")
+        .cloned()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| fallback_name.to_string());
+    let description = if is_markdown {
+        skill_description(&metadata, &body).0
+    } else {
+        String::new()
+    };
+
+    let modified_secs = |metadata: fs::Metadata| {
+        metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs())
+    };
+    let last_modified = fs::metadata(core_file_path)
+        .ok()
+        .and_then(modified_secs)
+        .max(
+            fs::symlink_metadata(core_file_path)
+                .ok()
+                .and_then(modified_secs),
+        );
+
+    Ok(SkillItem {
+        id: dir_name.to_string(),
+        name,
+        description,
+        path: skill_dir.display().to_string(),
+        core_file: core_file_name.to_string(),
+        core_file_path: core_file_path.display().to_string(),
+        source_url,
+        source_id: source.id.to_string(),
+        metadata,
+        body,
+        last_modified,
+    })
+}
+
+fn is_flat_skill_file(path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .and_then(|value| value.to_str())
+        .map(|value| value.starts_with('.'))
+        .unwrap_or(true);
+    let markdown = path
+        .extension()
+        .and_then(|value| value.to_str())
+        .map(|value| value.eq_ignore_ascii_case("md"))
+        .unwrap_or(false);
+    !hidden && markdown && path.is_file()
+}
+
+fn count_skills(source: &SourceConfig) -> usize {
+    let Ok(entries) = fs::read_dir(&source.root) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                false
+            } else if entry.file_type().is_ok_and(|file_type| !file_type.is_dir()) {
+                source.flat && is_flat_skill_file(&path)
+            } else {
+                find_core_file(&path, &source.core_files).is_some()
+            }
+        })
+        .count()
+}
+
+fn read_skills(source: &SourceConfig) -> Vec<SkillItem> {
+    let mut skills = Vec::new();
+
+    let entries = match fs::read_dir(&source.root) {
+        Ok(entries) => entries,
+        Err(_) => return skills,
+    };
+
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if let Ok(file_type) = entry.file_type() {
+            if !file_type.is_dir() {
+                if source.flat && is_flat_skill_file(&entry.path()) {
+                    let path = entry.path();
+                    let file_name = entry.file_name().to_string_lossy().to_string();
+                    if let Ok(skill) = load_skill(&path, &path, &file_name, source) {
+                        skills.push(skill);
+                    }
+                }
+                continue;
+            }
+        }
+
+        let path = entry.path();
+        let core_file = find_core_file(&path, &source.core_files);
+        if let Some((core_file_path, core_file_name)) = core_file {
+            if let Ok(skill) = load_skill(&path, &core_file_path, &core_file_name, source) {
+                skills.push(skill);
+            }
+        }
+    }
+
+    skills.sort_by(|a, b| {
+        a.name
+            .to_lowercase()
+            .cmp(&b.name.to_lowercase())
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    skills
+}
+
+fn resolve_skill_dir(
+    source: &SourceConfig,
+    skill_id: &str,
+    action: &str,
+) -> Result<PathBuf, String> {
+    let skill_dir = source.root.join(skill_id);
+    if !skill_dir.exists() {
+        return Err("Skill not found".to_string());
+    }
+
+    let root_canon =
+        fs::canonicalize(&source.root).map_err(|err| format!("Failed to resolve root: {}", err))?;
+    let skill_canon =
+        fs::canonicalize(&skill_dir).map_err(|err| format!("Failed to resolve skill: {}", err))?;
+    if !skill_canon.starts_with(&root_canon) {
+        return Err(format!("Refusing to {} outside agent root", action));
+    }
+    log::debug!("resolved skill dir {}", skill_dir.display());
+    Ok(skill_dir)
+}
+
+fn collect_skill_files(
+    dir: &Path,
+    prefix: &str,
+    files: &mut Vec<(String, PathBuf)>,
+) -> Result<(), String> {
+    if prefix.is_empty() && dir.is_file() {
+        let name = dir
+            .file_name()
+            .map(|value| value.to_string_lossy().to_string())
+            .unwrap_or_default();
+        files.push((name, dir.to_path_buf()));
+        return Ok(());
+    }
+    let entries =
+        fs::read_dir(dir).map_err(|err| format!("Failed to read {}: {}", dir.display(), err))?;
+    for entry in entries {
+        let entry = entry.map_err(|err| format!("Failed to read entry: {}", err))?;
+        let file_type = entry
+            .file_type()
+            .map_err(|err| format!("Failed to read file type: {}", err))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if prefix.is_empty() && name == SKILL_SOURCE_FILENAME {
+            continue;
+        }
+        let relative = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_skill_files(&path, &relative, files)?;
+        } else if path.is_file() {
+            files.push((relative, path));
+        }
+    }
+    Ok(())
+}
+
+fn hash_bytes(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn skill_file_hashes(skill_dir: &Path) -> Result<HashMap<String, String>, String> {
+    let mut files = Vec::new();
+    collect_skill_files(skill_dir, "", &mut files)?;
+    files
+        .into_iter()
+        .map(|(relative, path)| {
+            fs::read(&path)
+                .map(|bytes| (relative, hash_bytes(&bytes)))
+                .map_err(|err| format!("Failed to read {}: {}", path.display(), err))
+        })
+        .collect()
+}
+
+fn compute_skill_hash(skill_dir: &Path) -> Result<String, String> {
+    let mut files = Vec::new();
+    collect_skill_files(skill_dir, "", &mut files)?;
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut hasher = Sha256::new();
+    for (relative, path) in files {
+        let bytes =
+            fs::read(&path).map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        hasher.update(relative.as_bytes());
+        hasher.update([0u8]);
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(&bytes);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[derive(Default)]
+struct SkillIgnore {
+    patterns: Vec<(glob::Pattern, bool)>,
+}
+
+impl SkillIgnore {
+    fn load(skill_dir: &Path) -> Self {
+        let content = fs::read_to_string(skill_dir.join(SKILL_IGNORE_FILENAME)).unwrap_or_default();
+        let patterns = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let line = line.trim_end_matches('/');
+                let anchored = line.contains('/');
+                glob::Pattern::new(line.trim_start_matches('/'))
+                    .ok()
+                    .map(|pattern| (pattern, anchored))
+            })
+            .collect();
+        SkillIgnore { patterns }
+    }
+
+    fn is_ignored(&self, relative: &str) -> bool {
+        let components: Vec<&str> = relative.split('/').collect();
+        self.patterns.iter().any(|(pattern, anchored)| {
+            if *anchored {
+                (1..=components.len()).any(|len| pattern.matches(&components[..len].join("/")))
+            } else {
+                components
+                    .iter()
+                    .any(|component| pattern.matches(component))
+            }
+        })
+    }
+}
+
+fn copy_dir_recursive(src: &Path, dest: &Path) -> Result<(), String> {
+    copy_dir_filtered(src, dest, "", &SkillIgnore::load(src))
+}
+
+fn copy_dir_filtered(
+    src: &Path,
+    dest: &Path,
+    prefix: &str,
+    ignore: &SkillIgnore,
+) -> Result<(), String> {
+    fs::create_dir_all(dest)
+        .map_err(|err| format!("Failed to create {}: {}", dest.display(), err))?;
+    let entries =
+        fs::read_dir(src).map_err(|err| format!("Failed to read {}: {}", src.display(), err))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|err| format!("Failed to read entry: {}", err))?;
+        let file_type = entry
+            .file_type()
+            .map_err(|err| format!("Failed to read file type: {}", err))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let relative = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        if ignore.is_ignored(&relative) {
+            continue;
+        }
+        let source_path = entry.path();
+        let target_path = dest.join(entry.file_name());
+
+        if file_type.is_dir() {
+            copy_dir_filtered(&source_path, &target_path, &relative, ignore)?;
+        } else {
+            fs::copy(&source_path, &target_path).map_err(|err| {
+                if err.kind() == std::io::ErrorKind::PermissionDenied {
+                    return write_error("File", &target_path, err);
+                }
+                format!(
+                    "Failed to copy {} to {}: {}",
+                    source_path.display(),
+                    target_path.display(),
+                    err
+                )
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+fn prune_skill_dir(
+    dir: &Path,
+    upstream: &Path,
+    prefix: &str,
+    ignore: &SkillIgnore,
+) -> Result<usize, String> {
+    let entries =
+        fs::read_dir(dir).map_err(|err| format!("Failed to read {}: {}", dir.display(), err))?;
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let relative = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        if (prefix.is_empty() && (name == SKILL_SOURCE_FILENAME || name == SKILL_IGNORE_FILENAME))
+            || ignore.is_ignored(&relative)
+        {
+            continue;
+        }
+        let path = entry.path();
+        let counterpart = upstream.join(&name);
+        let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+        if is_dir && counterpart.is_dir() {
+            removed += prune_skill_dir(&path, &counterpart, &relative, ignore)?;
+            continue;
+        }
+        if counterpart.exists() {
+            continue;
+        }
+        let result = if is_dir {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        result.map_err(|err| format!("Failed to remove {}: {}", path.display(), err))?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+fn apply_upstream_tree(upstream: &Path, skill_dir: &Path) -> Result<(), String> {
+    let ignore = SkillIgnore::load(skill_dir);
+    copy_dir_filtered(upstream, skill_dir, "", &ignore)?;
+    prune_skill_dir(skill_dir, upstream, "", &ignore)?;
+    Ok(())
+}
+
+fn copy_dir_incremental(src: &Path, dest: &Path, mirror: bool) -> Result<CopyStats, String> {
+    let mut stats = CopyStats::default();
+    copy_dir_newer(src, dest, "", &SkillIgnore::load(src), mirror, &mut stats)?;
+    Ok(stats)
+}
+
+fn copy_dir_newer(
+    src: &Path,
+    dest: &Path,
+    prefix: &str,
+    ignore: &SkillIgnore,
+    mirror: bool,
+    stats: &mut CopyStats,
+) -> Result<(), String> {
+    fs::create_dir_all(dest)
+        .map_err(|err| format!("Failed to create {}: {}", dest.display(), err))?;
+    let relative_name = |name: &str| {
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", prefix, name)
+        }
+    };
+    let entries =
+        fs::read_dir(src).map_err(|err| format!("Failed to read {}: {}", src.display(), err))?;
+    let mut seen = HashSet::new();
+
+    for entry in entries {
+        let entry = entry.map_err(|err| format!("Failed to read entry: {}", err))?;
+        let file_type = entry
+            .file_type()
+            .map_err(|err| format!("Failed to read file type: {}", err))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let relative = relative_name(&name);
+        if ignore.is_ignored(&relative) {
+            continue;
+        }
+        seen.insert(name);
+        let source_path = entry.path();
+        let target_path = dest.join(entry.file_name());
+
+        if file_type.is_dir() {
+            if target_path.is_file() {
+                fs::remove_file(&target_path).map_err(|err| {
+                    format!("Failed to remove {}: {}", target_path.display(), err)
+                })?;
+            }
+            copy_dir_newer(&source_path, &target_path, &relative, ignore, mirror, stats)?;
+            continue;
+        }
+        if target_path.is_dir() {
+            fs::remove_dir_all(&target_path)
+                .map_err(|err| format!("Failed to remove {}: {}", target_path.display(), err))?;
+        }
+        let modified = |path: &Path| fs::metadata(path).and_then(|data| data.modified());
+        let newer = match (modified(&source_path), modified(&target_path)) {
+            (Ok(source), Ok(target)) => source > target,
+            _ => true,
+        };
+        if newer {
+            fs::copy(&source_path, &target_path).map_err(|err| {
+                if err.kind() == std::io::ErrorKind::PermissionDenied {
+                    return write_error("File", &target_path, err);
+                }
+                format!(
+                    "Failed to copy {} to {}: {}",
+                    source_path.display(),
+                    target_path.display(),
+                    err
+                )
+            })?;
+            stats.copied += 1;
+        }
+    }
+
+    if mirror {
+        let entries = fs::read_dir(dest)
+            .map_err(|err| format!("Failed to read {}: {}", dest.display(), err))?;
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if seen.contains(&name)
+                || (prefix.is_empty() && name == SKILL_SOURCE_FILENAME)
+                || ignore.is_ignored(&relative_name(&name))
+            {
+                continue;
+            }
+            let path = entry.path();
+            let removed = if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            removed.map_err(|err| format!("Failed to remove {}: {}", path.display(), err))?;
+            stats.removed += 1;
+        }
+    }
+
+    Ok(())
+}
+
+fn find_core_file(skill_dir: &Path, core_files: &[&str]) -> Option<(PathBuf, String)> {
+    let mut dirs = vec![(skill_dir.to_path_buf(), String::new())];
+    for _ in 0..=MAX_CORE_FILE_DEPTH {
+        let mut next = Vec::new();
+        let mut found = Vec::new();
+        for (dir, prefix) in dirs {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            let mut files = Vec::new();
+            let mut subdirs = Vec::new();
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let path = entry.path();
+                if path.is_file() {
+                    files.push(name);
+                } else if path.is_dir() && !name.starts_with('.') {
+                    subdirs.push(name);
+                }
+            }
+            let core = core_files.iter().find_map(|file| {
+                files
+                    .iter()
+                    .find(|name| name.as_str() == *file)
+                    .or_else(|| files.iter().find(|name| name.eq_ignore_ascii_case(file)))
+            });
+            if let Some(name) = core {
+                found.push((dir.join(name), format!("{}{}", prefix, name)));
+                continue;
+            }
+            subdirs.sort();
+            for name in subdirs {
+                next.push((dir.join(&name), format!("{}{}/", prefix, name)));
+            }
+        }
+        // Several nested skills mean this is a collection, not one skill.
+        match found.len() {
+            0 => {}
+            1 => return found.pop(),
+            _ => return None,
+        }
+        dirs = next;
+    }
+    None
+}
+
+fn skill_core_file(skill_dir: &Path, source: &SourceConfig) -> Result<(PathBuf, String), String> {
+    if skill_dir.is_file() {
+        if !(source.flat && is_flat_skill_file(skill_dir)) {
+            return Err("Missing core file".to_string());
+        }
+        let name = skill_dir
+            .file_name()
+            .map(|value| value.to_string_lossy().to_string())
+            .unwrap_or_default();
+        return Ok((skill_dir.to_path_buf(), name));
+    }
+    find_core_file(skill_dir, &source.core_files).ok_or_else(|| "Missing core file".to_string())
+}
+
+fn require_skill_directory(skill_dir: &Path, action: &str) -> Result<(), String> {
+    if skill_dir.is_file() {
+        return Err(format!("Flat skill files cannot be {}", action));
+    }
+    Ok(())
+}
+
+fn build_skill_tree(path: &Path) -> Result<SkillTreeNode, String> {
+    build_skill_tree_within(path, &mut Vec::new())
+}
+
+fn build_skill_tree_within(
+    path: &Path,
+    ancestors: &mut Vec<PathBuf>,
+) -> Result<SkillTreeNode, String> {
+    let metadata = fs::symlink_metadata(path)
+        .map_err(|err| format!("Failed to read metadata {}: {}", path.display(), err))?;
+    let file_type = metadata.file_type();
+    let name = path
+        .file_name()
+        .and_then(|value| value.to_str())
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+
+    let kind = if file_type.is_dir() {
+        "dir"
+    } else if file_type.is_symlink() {
+        "link"
+    } else {
+        "file"
+    };
+
+    let mut children = Vec::new();
+    let mut truncated = false;
+    let mut pushed = false;
+    let is_dir = file_type.is_dir()
+        || (file_type.is_symlink() && fs::metadata(path).is_ok_and(|target| target.is_dir()));
+    let canonical = if is_dir {
+        fs::canonicalize(path).ok()
+    } else {
+        None
+    };
+    if let Some(canonical) = canonical {
+        let escapes_skill = ancestors
+            .first()
+            .is_some_and(|skill_root| !canonical.starts_with(skill_root));
+        if escapes_skill
+            || ancestors.contains(&canonical)
+            || ancestors.len() >= MAX_SKILL_TREE_DEPTH
+        {
+            truncated = true;
+        } else {
+            ancestors.push(canonical);
+            pushed = true;
+        }
+    }
+    if is_dir && !truncated {
+        let entries = fs::read_dir(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let mut items: Vec<_> = entries.filter_map(|entry| entry.ok()).collect();
+        items.sort_by(|a, b| {
+            let a_dir = a.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
+            let b_dir = b.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
+            if a_dir != b_dir {
+                return b_dir.cmp(&a_dir);
+            }
+            a.file_name()
+                .to_string_lossy()
+                .to_lowercase()
+                .cmp(&b.file_name().to_string_lossy().to_lowercase())
+        });
+
+        for entry in items {
+            let path = entry.path();
+            let child = build_skill_tree_within(&path, ancestors)?;
+            children.push(child);
+        }
+    }
+    if pushed {
+        ancestors.pop();
+    }
+
+    Ok(SkillTreeNode {
+        name,
+        path: path.display().to_string(),
+        kind: kind.to_string(),
+        children,
+        truncated,
+    })
+}
+
+fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    let mut last_dash = false;
+
+    for ch in name.chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+            last_dash = false;
+        } else if !last_dash {
+            slug.push('-');
+            last_dash = true;
+        }
+    }
+
+    let trimmed = slug.trim_matches('-').to_string();
+    if trimmed.is_empty() {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        return format!("skill-{}", stamp);
+    }
+    trimmed
+}
+
+fn parse_skill_urls(input: &str, core_file: &str) -> Result<Vec<String>, String> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err("URL is required".to_string());
+    }
+    if !trimmed.starts_with("http://") && !trimmed.starts_with("https://") {
+        return Err("URL must start with http:// or https://".to_string());
+    }
+
+    let parsed = Url::parse(trimmed).map_err(|_| "Invalid URL".to_string())?;
+    let host = parsed.host_str().unwrap_or("").trim_start_matches("www.");
+    let segments: Vec<&str> = parsed
+        .path()
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    if host == "raw.githubusercontent.com" {
+        let mut url = trimmed.trim_end_matches('/').to_string();
+        if !url.ends_with(core_file) {
+            url.push('/');
+            url.push_str(core_file);
+        }
+        return Ok(vec![url]);
+    }
+
+    if host == "github.com" {
+        if segments.len() < 2 {
+            return Err("GitHub URL must include owner and repo".to_string());
+        }
+        let owner = segments[0];
+        let repo = segments[1].trim_end_matches(".git");
+
+        if segments.len() >= 4 && (segments[2] == "tree" || segments[2] == "blob") {
+            let branch = segments[3];
+            let subpath = segments[4..].join("/");
+            let file_path = if subpath.is_empty() {
+                core_file.to_string()
+            } else if subpath.ends_with(core_file) {
+                subpath
+            } else {
+                format!("{}/{}", subpath, core_file)
+            };
+            let url = format!(
+                "https://raw.githubusercontent.com/{}/{}/{}/{}",
+                owner, repo, branch, file_path
+            );
+            return Ok(vec![url]);
+        }
+
+        let subpath = if segments.len() > 2 {
+            segments[2..].join("/")
+        } else {
+            String::new()
+        };
+        let file_path = if subpath.is_empty() {
+            core_file.to_string()
+        } else if subpath.ends_with(core_file) {
+            subpath
+        } else {
+            format!("{}/{}", subpath, core_file)
+        };
+        return Ok(fallback_branches()
+            .iter()
+            .map(|branch| {
+                format!(
+                    "https://raw.githubusercontent.com/{}/{}/{}/{}",
+                    owner, repo, branch, file_path
+                )
+            })
+            .collect());
+    }
+
+    let mut url = trimmed.trim_end_matches('/').to_string();
+    if !url.ends_with(core_file) {
+        url.push('/');
+        url.push_str(core_file);
+    }
+    Ok(vec![url])
+}
+
+fn read_skill_source(skill_dir: &Path) -> Option<SkillSourceRecord> {
+    let path = skill_dir.join(SKILL_SOURCE_FILENAME);
+    let content = fs::read_to_string(path).ok()?;
+    let record: SkillSourceRecord = serde_json::from_str(&content).ok()?;
+    if record.url.trim().is_empty() {
+        return None;
+    }
+    Some(record)
+}
+
+fn read_skill_source_url(skill_dir: &Path) -> Option<String> {
+    read_skill_source(skill_dir).map(|record| record.url)
+}
+
+fn write_skill_source(skill_dir: &Path, record: &SkillSourceRecord) -> Result<(), String> {
+    if record.url.trim().is_empty() {
+        return Ok(());
+    }
+    let path = skill_dir.join(SKILL_SOURCE_FILENAME);
+    let content = serde_json::to_string_pretty(record)
+        .map_err(|err| format!("Failed to serialize JSON: {}", err))?;
+    fs::write(&path, format!("{}\n", content)).map_err(|err| write_error("File", &path, err))?;
+    Ok(())
+}
+
+fn write_skill_source_url(skill_dir: &Path, url: &str) -> Result<(), String> {
+    write_skill_source(
+        skill_dir,
+        &SkillSourceRecord {
+            url: url.trim().to_string(),
+            branch: None,
+            core_hash: None,
         },
-        SourceConfig {
-            id: "kiro-user",
-            label: "Kiro",
-            install_root: home.join(".kiro"),
-            root: home.join(".kiro").join("skills"),
-            core_files: kiro_files.clone(),
+    )
+}
+
+fn parse_github_location(input: &str) -> Result<GithubLocation, String> {
+    let trimmed = input.trim();
+    let parsed = Url::parse(trimmed).map_err(|_| "Invalid URL".to_string())?;
+    let host = parsed.host_str().unwrap_or("").trim_start_matches("www.");
+    let segments: Vec<&str> = parsed
+        .path()
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    if host == "raw.githubusercontent.com" {
+        if segments.len() < 3 {
+            return Err("Raw GitHub URL must include owner/repo/branch".to_string());
+        }
+        let owner = segments[0].to_string();
+        let repo = segments[1].trim_end_matches(".git").to_string();
+        let branch = Some(segments[2].to_string());
+        let path = if segments.len() > 3 {
+            segments[3..].join("/")
+        } else {
+            String::new()
+        };
+        return Ok(GithubLocation {
+            owner,
+            repo,
+            branch,
+            path,
+        });
+    }
+
+    if !is_github_web_host(host) {
+        return Err("Not a GitHub URL".to_string());
+    }
+    if segments.len() < 2 {
+        return Err("GitHub URL must include owner and repo".to_string());
+    }
+
+    let owner = segments[0].to_string();
+    let repo = segments[1].trim_end_matches(".git").to_string();
+
+    if segments.len() >= 4 && (segments[2] == "tree" || segments[2] == "blob") {
+        let branch = Some(segments[3].to_string());
+        let mut path = segments[4..].join("/");
+        if segments[2] == "blob" {
+            if let Some((dir, _)) = path.rsplit_once('/') {
+                path = dir.to_string();
+            } else {
+                path.clear();
+            }
+        }
+        return Ok(GithubLocation {
+            owner,
+            repo,
+            branch,
+            path,
+        });
+    }
+
+    let path = if segments.len() > 2 {
+        segments[2..].join("/")
+    } else {
+        String::new()
+    };
+
+    Ok(GithubLocation {
+        owner,
+        repo,
+        branch: None,
+        path,
+    })
+}
+
+fn user_agent() -> String {
+    std::env::var("ANANKE_USER_AGENT")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string())
+}
+
+fn github_api_base() -> String {
+    std::env::var("ANANKE_GITHUB_API_BASE")
+        .ok()
+        .map(|value| value.trim().trim_end_matches('/').to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| DEFAULT_GITHUB_API_BASE.to_string())
+}
+
+fn github_api_version() -> String {
+    std::env::var("ANANKE_GITHUB_API_VERSION")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| DEFAULT_GITHUB_API_VERSION.to_string())
+}
+
+fn offline_mode() -> bool {
+    OFFLINE_MODE.load(Ordering::Relaxed) || env_flag("ANANKE_OFFLINE")
+}
+
+fn ensure_online() -> Result<(), String> {
+    if offline_mode() {
+        return Err("Offline mode is enabled; network requests are disabled".to_string());
+    }
+    Ok(())
+}
+
+fn github_tree_url(url: &str, fallback_branch: Option<&str>) -> Result<String, String> {
+    let location = parse_github_location(url)?;
+    let host = Url::parse(url.trim())
+        .ok()
+        .and_then(|parsed| {
+            parsed
+                .host_str()
+                .map(|host| host.trim_start_matches("www.").to_string())
+        })
+        .filter(|host| host != "raw.githubusercontent.com")
+        .unwrap_or_else(|| "github.com".to_string());
+    let mut canonical = format!("https://{}/{}/{}", host, location.owner, location.repo);
+    let branch = location.branch.as_deref().or(fallback_branch);
+    if branch.is_some() || !location.path.is_empty() {
+        canonical.push_str("/tree/");
+        canonical.push_str(branch.unwrap_or("HEAD"));
+    }
+    if !location.path.is_empty() {
+        canonical.push('/');
+        canonical.push_str(&location.path);
+    }
+    Ok(canonical)
+}
+
+fn is_github_web_host(host: &str) -> bool {
+    if host == "github.com" {
+        return true;
+    }
+    let api_base = github_api_base();
+    if api_base == DEFAULT_GITHUB_API_BASE {
+        return false;
+    }
+    Url::parse(&api_base)
+        .ok()
+        .and_then(|parsed| parsed.host_str().map(|value| value == host))
+        .unwrap_or(false)
+}
+
+fn http_agent() -> ureq::Agent {
+    ureq::AgentBuilder::new().user_agent(&user_agent()).build()
+}
+
+fn github_token() -> Option<String> {
+    let ui_token = TOKEN_OVERRIDE.with(|cell| cell.borrow().clone());
+    if let Some(token) = ui_token {
+        if !token.trim().is_empty() {
+            return Some(token);
+        }
+    }
+    if let Some(token) = SESSION_GITHUB_TOKEN
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone())
+    {
+        return Some(token);
+    }
+    github_token_env_var().and_then(|name| std::env::var(name).ok())
+}
+
+fn github_token_env_var() -> Option<&'static str> {
+    ["SKILL_GITHUB_TOKEN", "GITHUB_TOKEN", "GH_TOKEN"]
+        .into_iter()
+        .find(|name| {
+            std::env::var(name)
+                .map(|value| !value.trim().is_empty())
+                .unwrap_or(false)
+        })
+}
+
+struct TokenGuard;
+
+impl Drop for TokenGuard {
+    fn drop(&mut self) {
+        TOKEN_OVERRIDE.with(|cell| *cell.borrow_mut() = None);
+    }
+}
+
+fn override_github_token(token: Option<&str>) -> TokenGuard {
+    if let Some(token) = token {
+        TOKEN_OVERRIDE.with(|cell| *cell.borrow_mut() = Some(token.to_string()));
+    }
+    TokenGuard
+}
+
+fn github_request(agent: &ureq::Agent, url: &str) -> Result<ureq::Request, String> {
+    github_request_with_accept(agent, url, GITHUB_JSON_ACCEPT)
+}
+
+fn github_request_with_accept(
+    agent: &ureq::Agent,
+    url: &str,
+    accept: &str,
+) -> Result<ureq::Request, String> {
+    ensure_online()?;
+    log::debug!("GitHub request {} ({})", url, accept);
+    let mut request = agent
+        .get(url)
+        .set("Accept", accept)
+        .set("X-GitHub-Api-Version", &github_api_version());
+    if let Some(token) = github_token() {
+        request = request.set("Authorization", &format!("Bearer {}", token));
+    }
+    Ok(request)
+}
+
+fn read_json_response(response: ureq::Response) -> Result<JsonValue, String> {
+    let body = response
+        .into_string()
+        .map_err(|err| format!("Failed to read response: {}", err))?;
+    serde_json::from_str(&body).map_err(|err| format!("Invalid JSON: {}", err))
+}
+
+fn github_contents_url(owner: &str, repo: &str, path: &str, branch: &str) -> String {
+    if path.is_empty() {
+        format!(
+            "{}/repos/{}/{}/contents?ref={}",
+            github_api_base(),
+            owner,
+            repo,
+            branch
+        )
+    } else {
+        format!(
+            "{}/repos/{}/{}/contents/{}?ref={}",
+            github_api_base(),
+            owner,
+            repo,
+            path,
+            branch
+        )
+    }
+}
+
+fn fetch_github_default_branch(
+    agent: &ureq::Agent,
+    owner: &str,
+    repo: &str,
+) -> Result<String, String> {
+    fetch_github_default_branch_from(agent, &github_api_base(), owner, repo)
+}
+
+fn fetch_github_default_branch_from(
+    agent: &ureq::Agent,
+    api_base: &str,
+    owner: &str,
+    repo: &str,
+) -> Result<String, String> {
+    let cache = DEFAULT_BRANCHES.get_or_init(|| Mutex::new(HashMap::new()));
+    let key = format!("{}/{}", owner, repo).to_ascii_lowercase();
+    if let Some(branch) = cache.lock().ok().and_then(|cache| {
+        cache
+            .get(&key)
+            .filter(|(fetched_at, _)| unix_timestamp() < fetched_at + DEFAULT_BRANCH_CACHE_SECS)
+            .map(|(_, branch)| branch.clone())
+    }) {
+        return Ok(branch);
+    }
+
+    let url = format!("{}/repos/{}/{}", api_base, owner, repo);
+    let mut attempt = 1;
+    let response = loop {
+        match github_request(agent, &url)?.call() {
+            Ok(response) => break response,
+            Err(ureq::Error::Status(code, _))
+                if code < 500 || attempt >= DEFAULT_BRANCH_ATTEMPTS =>
+            {
+                return Err(format!("Failed to read GitHub repo info: status {}", code));
+            }
+            Err(err) if attempt >= DEFAULT_BRANCH_ATTEMPTS => {
+                return Err(format!("Failed to read GitHub repo info: {}", err));
+            }
+            Err(err) => {
+                log::debug!("retrying GitHub repo info for {}: {}", key, err);
+                std::thread::sleep(Duration::from_millis(250 * attempt));
+                attempt += 1;
+            }
+        }
+    };
+    let value =
+        read_json_response(response).map_err(|err| format!("Invalid GitHub response: {}", err))?;
+    let branch = value
+        .get("default_branch")
+        .and_then(|item| item.as_str())
+        .map(|item| item.to_string())
+        .ok_or_else(|| "Missing default_branch in GitHub response".to_string())?;
+    if let Ok(mut cache) = cache.lock() {
+        cache.insert(key, (unix_timestamp(), branch.clone()));
+    }
+    Ok(branch)
+}
+
+fn fetch_github_branches(
+    agent: &ureq::Agent,
+    owner: &str,
+    repo: &str,
+) -> Result<Vec<String>, String> {
+    let mut branches = Vec::new();
+    let mut page = 1;
+    loop {
+        let url = format!(
+            "{}/repos/{}/{}/branches?per_page=100&page={}",
+            github_api_base(),
+            owner,
+            repo,
+            page
+        );
+        let response = github_request(agent, &url)?
+            .call()
+            .map_err(|err| format!("Failed to read GitHub branches: {}", err))?;
+        let value = read_json_response(response)
+            .map_err(|err| format!("Invalid GitHub response: {}", err))?;
+        let items = value
+            .as_array()
+            .ok_or_else(|| "Unexpected GitHub response".to_string())?;
+        for item in items {
+            if let Some(name) = item.get("name").and_then(|name| name.as_str()) {
+                branches.push(name.to_string());
+            }
+        }
+        if items.len() < 100 {
+            break;
+        }
+        page += 1;
+    }
+    Ok(branches)
+}
+
+fn fallback_branches() -> &'static [&'static str] {
+    if env_flag("ANANKE_NO_MASTER_FALLBACK") {
+        &["main"]
+    } else {
+        &["main", "master"]
+    }
+}
+
+fn github_branch_candidates(agent: &ureq::Agent, location: &GithubLocation) -> Vec<String> {
+    if let Some(branch) = &location.branch {
+        return vec![branch.clone()];
+    }
+    match fetch_github_default_branch(agent, &location.owner, &location.repo) {
+        Ok(default_branch) => vec![default_branch],
+        Err(err) => {
+            log::warn!("falling back to common branch names: {}", err);
+            fallback_branches()
+                .iter()
+                .map(|branch| branch.to_string())
+                .collect()
+        }
+    }
+}
+
+fn branches_tried_error(what: &str, branches: &[String], last_error: Option<String>) -> String {
+    let label = if branches.len() == 1 {
+        "branch"
+    } else {
+        "branches"
+    };
+    let mut message = format!(
+        "Unable to find {} on {} {}",
+        what,
+        label,
+        branches.join(", ")
+    );
+    if let Some(err) = last_error {
+        message.push_str(&format!(" ({})", err));
+    }
+    message
+}
+
+fn fetch_github_contents(
+    agent: &ureq::Agent,
+    owner: &str,
+    repo: &str,
+    path: &str,
+    branch: &str,
+) -> Result<Vec<GithubContentEntry>, String> {
+    let url = github_contents_url(owner, repo, path, branch);
+    let response = github_request(agent, &url)?
+        .call()
+        .map_err(|err| format!("Failed to read GitHub contents: {}", err))?;
+    let value =
+        read_json_response(response).map_err(|err| format!("Invalid GitHub response: {}", err))?;
+    match value {
+        JsonValue::Array(items) => items
+            .into_iter()
+            .map(|item| {
+                serde_json::from_value(item).map_err(|err| format!("Invalid GitHub entry: {}", err))
+            })
+            .collect(),
+        JsonValue::Object(_) => {
+            let entry: GithubContentEntry = serde_json::from_value(value)
+                .map_err(|err| format!("Invalid GitHub entry: {}", err))?;
+            Ok(vec![entry])
+        }
+        _ => Err("Unexpected GitHub response".to_string()),
+    }
+}
+
+fn decode_base64_payload(content: &str) -> Result<Vec<u8>, String> {
+    let cleaned = content.replace('\n', "");
+    base64::engine::general_purpose::STANDARD
+        .decode(cleaned.as_bytes())
+        .map_err(|err| format!("Failed to decode base64 payload: {}", err))
+}
+
+fn fetch_github_raw(agent: &ureq::Agent, url: &str, what: &str) -> Result<Option<Vec<u8>>, String> {
+    let response = github_request_with_accept(agent, url, GITHUB_RAW_ACCEPT)?
+        .call()
+        .map_err(|err| format!("Failed to read {}: {}", what, err))?;
+    if !response
+        .content_type()
+        .starts_with("application/vnd.github.raw")
+    {
+        log::debug!("GitHub ignored raw media type for {}", url);
+        return Ok(None);
+    }
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .map_err(|err| format!("Failed to read {}: {}", what, err))?;
+    Ok(Some(bytes))
+}
+
+fn fetch_github_blob_content(
+    agent: &ureq::Agent,
+    owner: &str,
+    repo: &str,
+    sha: &str,
+) -> Result<Vec<u8>, String> {
+    let url = format!(
+        "{}/repos/{}/{}/git/blobs/{}",
+        github_api_base(),
+        owner,
+        repo,
+        sha
+    );
+    if let Some(bytes) = fetch_github_raw(agent, &url, "GitHub blob")? {
+        return Ok(bytes);
+    }
+    let response = github_request(agent, &url)?
+        .call()
+        .map_err(|err| format!("Failed to read GitHub blob: {}", err))?;
+    let value =
+        read_json_response(response).map_err(|err| format!("Invalid GitHub response: {}", err))?;
+    let obj = value
+        .as_object()
+        .ok_or_else(|| "Invalid GitHub blob response".to_string())?;
+    let content = obj
+        .get("content")
+        .and_then(|item| item.as_str())
+        .ok_or_else(|| "Missing content in GitHub blob response".to_string())?;
+    let encoding = obj
+        .get("encoding")
+        .and_then(|item| item.as_str())
+        .unwrap_or("base64");
+    if encoding != "base64" {
+        return Err("Unsupported GitHub blob encoding".to_string());
+    }
+    decode_base64_payload(content)
+}
+
+fn fetch_github_file_content(
+    agent: &ureq::Agent,
+    owner: &str,
+    repo: &str,
+    path: &str,
+    branch: &str,
+) -> Result<Vec<u8>, String> {
+    let url = github_contents_url(owner, repo, path, branch);
+    if let Some(bytes) = fetch_github_raw(agent, &url, "GitHub file")? {
+        return Ok(bytes);
+    }
+    let response = github_request(agent, &url)?
+        .call()
+        .map_err(|err| format!("Failed to read GitHub file: {}", err))?;
+    let value =
+        read_json_response(response).map_err(|err| format!("Invalid GitHub response: {}", err))?;
+    let obj = value
+        .as_object()
+        .ok_or_else(|| "Invalid GitHub file response".to_string())?;
+    if let Some(content) = obj.get("content").and_then(|item| item.as_str()) {
+        let encoding = obj
+            .get("encoding")
+            .and_then(|item| item.as_str())
+            .unwrap_or("base64");
+        if encoding != "base64" {
+            return Err("Unsupported GitHub file encoding".to_string());
+        }
+        return decode_base64_payload(content);
+    }
+    if let Some(sha) = obj.get("sha").and_then(|item| item.as_str()) {
+        return fetch_github_blob_content(agent, owner, repo, sha);
+    }
+    Err("Missing content in GitHub file response".to_string())
+}
+
+fn write_bytes_to_path(bytes: &[u8], dest_path: &Path) -> Result<(), String> {
+    if let Some(parent) = dest_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|err| format!("Failed to create {}: {}", parent.display(), err))?;
+    }
+    fs::write(dest_path, bytes).map_err(|err| write_error("File", dest_path, err))?;
+    Ok(())
+}
+
+fn download_github_directory(
+    agent: &ureq::Agent,
+    location: &GithubLocation,
+    branch: &str,
+    core_file: &str,
+    dest_dir: &Path,
+) -> Result<DownloadStats, String> {
+    let mut files = Vec::new();
+    collect_github_directory_files(
+        agent,
+        &location.owner,
+        &location.repo,
+        branch,
+        &github_skill_dir_path(location, core_file),
+        dest_dir,
+        &mut files,
+    )?;
+    download_github_files(agent, &location.owner, &location.repo, branch, &files)
+}
+
+fn collect_github_directory_files(
+    agent: &ureq::Agent,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+    repo_path: &str,
+    dest_dir: &Path,
+    files: &mut Vec<(GithubContentEntry, PathBuf)>,
+) -> Result<(), String> {
+    fs::create_dir_all(dest_dir)
+        .map_err(|err| format!("Failed to create {}: {}", dest_dir.display(), err))?;
+    let entries = fetch_github_contents(agent, owner, repo, repo_path, branch)?;
+    for entry in entries {
+        match entry.item_type.as_str() {
+            "dir" => {
+                let next_dest = dest_dir.join(&entry.name);
+                collect_github_directory_files(
+                    agent,
+                    owner,
+                    repo,
+                    branch,
+                    &entry.path,
+                    &next_dest,
+                    files,
+                )?;
+            }
+            "file" => {
+                let dest_path = dest_dir.join(&entry.name);
+                files.push((entry, dest_path));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Maximum parallel file fetches per directory download, from
+/// `ANANKE_DOWNLOAD_CONCURRENCY` (default 4; `1` downloads sequentially).
+fn download_concurrency() -> usize {
+    std::env::var("ANANKE_DOWNLOAD_CONCURRENCY")
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(DEFAULT_DOWNLOAD_CONCURRENCY)
+}
+
+fn download_github_file(
+    agent: &ureq::Agent,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+    entry: &GithubContentEntry,
+    dest_path: &Path,
+) -> Result<u64, String> {
+    let bytes = if let Some(sha) = entry.sha.as_deref() {
+        fetch_github_blob_content(agent, owner, repo, sha)?
+    } else {
+        fetch_github_file_content(agent, owner, repo, &entry.path, branch)?
+    };
+    write_bytes_to_path(&bytes, dest_path)?;
+    Ok(bytes.len() as u64)
+}
+
+fn download_github_files(
+    agent: &ureq::Agent,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+    files: &[(GithubContentEntry, PathBuf)],
+) -> Result<DownloadStats, String> {
+    let workers = download_concurrency().min(files.len());
+    if workers <= 1 {
+        let mut stats = DownloadStats::default();
+        for (entry, dest_path) in files {
+            stats.bytes += download_github_file(agent, owner, repo, branch, entry, dest_path)?;
+            stats.files += 1;
+        }
+        return Ok(stats);
+    }
+
+    let token = TOKEN_OVERRIDE.with(|cell| cell.borrow().clone());
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let results: Vec<Result<DownloadStats, String>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let _guard = override_github_token(token.as_deref());
+                    let mut stats = DownloadStats::default();
+                    while !failed.load(Ordering::Relaxed) {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some((entry, dest_path)) = files.get(index) else {
+                            break;
+                        };
+                        match download_github_file(agent, owner, repo, branch, entry, dest_path) {
+                            Ok(bytes) => {
+                                stats.files += 1;
+                                stats.bytes += bytes;
+                            }
+                            Err(err) => {
+                                failed.store(true, Ordering::Relaxed);
+                                return Err(err);
+                            }
+                        }
+                    }
+                    Ok(stats)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err("Download worker panicked".to_string()))
+            })
+            .collect()
+    });
+
+    let mut stats = DownloadStats::default();
+    for result in results {
+        let worker = result?;
+        stats.files += worker.files;
+        stats.bytes += worker.bytes;
+    }
+    Ok(stats)
+}
+
+fn github_skill_dir_path(location: &GithubLocation, core_file: &str) -> String {
+    let core_name = core_file.rsplit('/').next().unwrap_or(core_file);
+    match location.path.rsplit_once('/') {
+        Some((dir, last)) if last.eq_ignore_ascii_case(core_name) => dir.to_string(),
+        None if location.path.eq_ignore_ascii_case(core_name) => String::new(),
+        _ => location.path.clone(),
+    }
+}
+
+fn github_file_path(location: &GithubLocation, core_file: &str) -> String {
+    let dir = github_skill_dir_path(location, core_file);
+    if dir != location.path {
+        location.path.clone()
+    } else if dir.is_empty() {
+        core_file.to_string()
+    } else {
+        format!("{}/{}", dir, core_file)
+    }
+}
+
+fn fallback_name_from_url(input: &str, core_file: &str) -> String {
+    if let Ok(parsed) = Url::parse(input) {
+        let mut segments: Vec<&str> = parsed
+            .path()
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        if let Some(last) = segments.pop() {
+            if last.eq_ignore_ascii_case(core_file) {
+                if let Some(prev) = segments.pop() {
+                    return prev.to_string();
+                }
+            }
+            return last.to_string();
+        }
+    }
+    "skill".to_string()
+}
+
+fn looks_like_html(body: &str) -> bool {
+    let head: String = body
+        .trim_start_matches('\u{feff}')
+        .trim_start()
+        .chars()
+        .take(256)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    head.starts_with("<!doctype html")
+        || head.starts_with("<html")
+        || (head.starts_with('<') && (head.contains("<head") || head.contains("<body")))
+}
+
+fn fetch_skill_content(urls: Vec<String>, core_file: &str) -> Result<String, String> {
+    ensure_online()?;
+    let agent = http_agent();
+    let mut last_error = None;
+
+    for url in urls {
+        log::debug!("skill download request {}", url);
+        match agent.get(&url).call() {
+            Ok(response) => {
+                if response.status() == 200 {
+                    let content_type = response.content_type().to_ascii_lowercase();
+                    let body = response
+                        .into_string()
+                        .map_err(|err| format!("Failed to read response: {}", err))?;
+                    if body.trim().is_empty() {
+                        return Err(format!("{} is empty", core_file));
+                    }
+                    if content_type == "text/html"
+                        || content_type == "application/xhtml+xml"
+                        || looks_like_html(&body)
+                    {
+                        last_error = Some(format!(
+                            "{} returned an HTML page instead of {}",
+                            url, core_file
+                        ));
+                        continue;
+                    }
+                    return Ok(body);
+                }
+                last_error = Some(format!("Unexpected status {}", response.status()));
+            }
+            Err(err) => {
+                last_error = Some(format!("Request failed: {}", err));
+            }
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| format!("Unable to download {}", core_file)))
+}
+
+fn line_col_from_index(input: &str, index: usize) -> (usize, usize) {
+    let mut line = 1usize;
+    let mut col = 1usize;
+
+    for (offset, ch) in input.char_indices() {
+        if offset >= index {
+            break;
+        }
+        if ch == '\n' {
+            line += 1;
+            col = 1;
+        } else if ch != '\r' {
+            col += 1;
+        }
+    }
+
+    (line, col)
+}
+
+fn strip_bom(content: &str) -> &str {
+    content.strip_prefix('\u{feff}').unwrap_or(content)
+}
+
+fn load_toml_value(path: &Path) -> Result<TomlValue, String> {
+    if !path.exists() {
+        return Ok(TomlValue::Table(TomlMap::new()));
+    }
+    let raw = fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+    let content = strip_bom(&raw);
+    if content.trim().is_empty() {
+        return Ok(TomlValue::Table(TomlMap::new()));
+    }
+    content.parse::<TomlValue>().map_err(|err| {
+        let location = err
+            .span()
+            .map(|span| {
+                let (line, col) = line_col_from_index(content, span.start);
+                format!("line {}, column {}", line, col)
+            })
+            .unwrap_or_else(|| "unknown location".to_string());
+        format!("Invalid TOML in {} ({}): {}", path.display(), location, err)
+    })
+}
+
+fn save_toml_value(path: &Path, value: &TomlValue) -> Result<(), String> {
+    log::info!("writing {}", path.display());
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|err| format!("Failed to create {}: {}", parent.display(), err))?;
+    }
+    let content = toml::to_string_pretty(value)
+        .map_err(|err| format!("Failed to serialize TOML: {}", err))?;
+    fs::write(path, content).map_err(|err| write_error("Config file", path, err))
+}
+
+fn write_error(label: &str, path: &Path, err: std::io::Error) -> String {
+    if err.kind() == std::io::ErrorKind::PermissionDenied {
+        return format!(
+            "{} is read-only; check permissions on {}",
+            label,
+            path.display()
+        );
+    }
+    format!("Failed to write {}: {}", path.display(), err)
+}
+
+fn load_json_value(path: &Path) -> Result<JsonValue, String> {
+    if !path.exists() {
+        return Ok(JsonValue::Object(JsonMap::new()));
+    }
+    let raw = fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+    let content = strip_bom(&raw);
+    if content.trim().is_empty() {
+        return Ok(JsonValue::Object(JsonMap::new()));
+    }
+    serde_json::from_str(content).map_err(|err| {
+        format!(
+            "Invalid JSON in {} (line {}, column {}): {}",
+            path.display(),
+            err.line(),
+            err.column(),
+            err
+        )
+    })
+}
+
+fn save_json_value(path: &Path, value: &JsonValue) -> Result<(), String> {
+    log::info!("writing {}", path.display());
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|err| format!("Failed to create {}: {}", parent.display(), err))?;
+    }
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|err| format!("Failed to serialize JSON: {}", err))?;
+    fs::write(path, format!("{}\n", content)).map_err(|err| write_error("Config file", path, err))
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn backup_file(path: &Path) -> Result<Option<PathBuf>, String> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let file_name = path
+        .file_name()
+        .and_then(|value| value.to_str())
+        .unwrap_or("config");
+    let backup_path = path.with_file_name(format!("{}.{}.bak", file_name, unix_timestamp()));
+    fs::copy(path, &backup_path)
+        .map_err(|err| format!("Failed to back up {}: {}", path.display(), err))?;
+    Ok(Some(backup_path))
+}
+
+fn toml_to_json(value: &TomlValue) -> JsonValue {
+    match value {
+        TomlValue::String(value) => JsonValue::String(value.clone()),
+        TomlValue::Integer(value) => JsonValue::Number((*value).into()),
+        TomlValue::Float(value) => {
+            JsonValue::Number(serde_json::Number::from_f64(*value).unwrap_or_else(|| 0.into()))
+        }
+        TomlValue::Boolean(value) => JsonValue::Bool(*value),
+        TomlValue::Datetime(value) => JsonValue::String(value.to_string()),
+        TomlValue::Array(values) => JsonValue::Array(values.iter().map(toml_to_json).collect()),
+        TomlValue::Table(table) => {
+            let mut map = JsonMap::new();
+            for (key, value) in table {
+                map.insert(key.clone(), toml_to_json(value));
+            }
+            JsonValue::Object(map)
+        }
+    }
+}
+
+fn json_to_toml(value: &JsonValue) -> Result<TomlValue, String> {
+    match value {
+        JsonValue::Null => Err("Null values are not supported".to_string()),
+        JsonValue::Bool(value) => Ok(TomlValue::Boolean(*value)),
+        JsonValue::Number(value) => {
+            if let Some(int) = value.as_i64() {
+                Ok(TomlValue::Integer(int))
+            } else if let Some(float) = value.as_f64() {
+                Ok(TomlValue::Float(float))
+            } else {
+                Err("Unsupported number".to_string())
+            }
+        }
+        JsonValue::String(value) => Ok(TomlValue::String(value.clone())),
+        JsonValue::Array(values) => {
+            let mut array = Vec::new();
+            for value in values {
+                array.push(json_to_toml(value)?);
+            }
+            Ok(TomlValue::Array(array))
+        }
+        JsonValue::Object(values) => {
+            let mut table = TomlMap::new();
+            for (key, value) in values {
+                table.insert(key.clone(), json_to_toml(value)?);
+            }
+            Ok(TomlValue::Table(table))
+        }
+    }
+}
+
+fn validate_mcp_server_config(config: &JsonValue) -> Result<(), String> {
+    let obj = config
+        .as_object()
+        .ok_or_else(|| "server config must be an object".to_string())?;
+    let has_url = ["url", "serverUrl"]
+        .iter()
+        .any(|key| obj.get(*key).is_some_and(|value| value.is_string()));
+    match obj.get("command") {
+        Some(JsonValue::String(command)) if command.trim().is_empty() => {
+            return Err("command must not be empty".to_string());
+        }
+        Some(JsonValue::String(_)) => {}
+        Some(JsonValue::Array(items))
+            if !items.is_empty() && items.iter().all(JsonValue::is_string) => {}
+        Some(_) => return Err("command must be a string or an array of strings".to_string()),
+        None if !has_url => return Err("either command or url is required".to_string()),
+        None => {}
+    }
+    if let Some(args) = obj.get("args") {
+        if !args
+            .as_array()
+            .is_some_and(|items| items.iter().all(JsonValue::is_string))
+        {
+            return Err("args must be an array of strings".to_string());
+        }
+    }
+    if let Some(env) = obj.get("env") {
+        if !env.is_object() {
+            return Err("env must be an object".to_string());
+        }
+    }
+    if let Some(message_url) = obj.get(SSE_MESSAGE_URL_KEY) {
+        if !message_url.is_string() {
+            return Err(format!("{} must be a string", SSE_MESSAGE_URL_KEY));
+        }
+        if !has_url {
+            return Err(format!("{} requires url", SSE_MESSAGE_URL_KEY));
+        }
+    }
+    Ok(())
+}
+
+fn validate_mcp_servers(servers: &HashMap<String, JsonValue>) -> Result<(), String> {
+    let mut ids: Vec<&String> = servers.keys().collect();
+    ids.sort();
+    for id in ids {
+        validate_mcp_server_config(&servers[id]).map_err(|err| format!("{}: {}", id, err))?;
+    }
+    Ok(())
+}
+
+fn parse_mcp_json(input: &str) -> Result<HashMap<String, JsonValue>, String> {
+    let value: JsonValue =
+        serde_json::from_str(input).map_err(|err| format!("Invalid MCP JSON: {}", err))?;
+    let servers = value
+        .get("mcpServers")
+        .and_then(|item| item.as_object())
+        .ok_or_else(|| "mcpServers object missing".to_string())?;
+
+    let mut results = HashMap::new();
+    for (id, config) in servers {
+        results.insert(id.to_string(), config.clone());
+    }
+
+    Ok(results)
+}
+
+fn parse_mcp_inputs(input: &str) -> Result<Vec<JsonValue>, String> {
+    let value: JsonValue =
+        serde_json::from_str(input).map_err(|err| format!("Invalid MCP JSON: {}", err))?;
+    match value.get("inputs") {
+        None | Some(JsonValue::Null) => Ok(vec![]),
+        Some(JsonValue::Array(items)) => Ok(items.clone()),
+        Some(_) => Err("inputs must be an array".to_string()),
+    }
+}
+
+fn collect_input_references(value: &JsonValue, refs: &mut Vec<String>) {
+    match value {
+        JsonValue::String(text) => {
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("${input:") {
+                let after = &rest[start + "${input:".len()..];
+                let Some(end) = after.find('}') else {
+                    break;
+                };
+                let id = after[..end].trim().to_string();
+                if !id.is_empty() && !refs.contains(&id) {
+                    refs.push(id);
+                }
+                rest = &after[end + 1..];
+            }
+        }
+        JsonValue::Array(items) => {
+            for item in items {
+                collect_input_references(item, refs);
+            }
+        }
+        JsonValue::Object(map) => {
+            for item in map.values() {
+                collect_input_references(item, refs);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn input_id(input: &JsonValue) -> Option<&str> {
+    input.get("id").and_then(|item| item.as_str())
+}
+
+fn supports_mcp_inputs(config: &McpSourceConfig) -> bool {
+    matches!(config.kind, McpKind::ClaudeJson)
+}
+
+fn read_mcp_inputs(config: &McpSourceConfig, path: &Path) -> Result<Vec<JsonValue>, String> {
+    if !supports_mcp_inputs(config) || !path.exists() {
+        return Ok(vec![]);
+    }
+    let value = load_json_value(path)?;
+    Ok(value
+        .get("inputs")
+        .and_then(|item| item.as_array())
+        .cloned()
+        .unwrap_or_default())
+}
+
+fn merge_mcp_inputs(config: &McpSourceConfig, inputs: Vec<JsonValue>) -> Result<(), String> {
+    if inputs.is_empty() || !supports_mcp_inputs(config) {
+        return Ok(());
+    }
+    let mut value = load_json_value(&config.primary_path)?;
+    let root = value
+        .as_object_mut()
+        .ok_or_else(|| "Invalid JSON format".to_string())?;
+    let inputs_value = root
+        .entry("inputs".to_string())
+        .or_insert_with(|| JsonValue::Array(vec![]));
+    let existing = inputs_value
+        .as_array_mut()
+        .ok_or_else(|| "Invalid inputs format".to_string())?;
+
+    for input in inputs {
+        let position = input_id(&input)
+            .and_then(|id| existing.iter().position(|item| input_id(item) == Some(id)));
+        match position {
+            Some(index) => existing[index] = input,
+            None => existing.push(input),
+        }
+    }
+
+    save_json_value(&config.primary_path, &value)
+}
+
+fn opencode_to_standard_config(config: &JsonValue) -> JsonValue {
+    let Some(obj) = config.as_object() else {
+        return config.clone();
+    };
+    let mut out = JsonMap::new();
+
+    if let Some(url) = obj.get("url") {
+        out.insert("url".to_string(), url.clone());
+    }
+
+    if let Some(command) = obj.get("command") {
+        match command {
+            JsonValue::Array(items) => {
+                if let Some(first) = items.first().and_then(|item| item.as_str()) {
+                    out.insert("command".to_string(), JsonValue::String(first.to_string()));
+                    let args: Vec<JsonValue> = items
+                        .iter()
+                        .skip(1)
+                        .filter_map(|item| {
+                            item.as_str()
+                                .map(|value| JsonValue::String(value.to_string()))
+                        })
+                        .collect();
+                    if !args.is_empty() {
+                        out.insert("args".to_string(), JsonValue::Array(args));
+                    }
+                }
+            }
+            JsonValue::String(value) => {
+                out.insert("command".to_string(), JsonValue::String(value.to_string()));
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(enabled) = obj.get("enabled") {
+        out.insert("enabled".to_string(), enabled.clone());
+    }
+
+    if let Some(server_type) = obj.get("type") {
+        out.insert("type".to_string(), server_type.clone());
+    }
+
+    if let Some(env) = merge_opencode_env(obj.get("env"), obj.get("environment")) {
+        out.insert("env".to_string(), env);
+    }
+
+    for (key, value) in obj {
+        if ["command", "url", "enabled", "type", "env", "environment"].contains(&key.as_str()) {
+            continue;
+        }
+        out.insert(key.clone(), value.clone());
+    }
+
+    JsonValue::Object(out)
+}
+
+fn antigravity_to_standard_config(config: &JsonValue) -> JsonValue {
+    let Some(obj) = config.as_object() else {
+        return config.clone();
+    };
+    let mut out = JsonMap::new();
+
+    for (key, value) in obj {
+        if key != "serverUrl" && key != "url" {
+            out.insert(key.clone(), value.clone());
+        }
+    }
+
+    match (obj.get("serverUrl"), obj.get("url")) {
+        (Some(server_url), Some(url)) => {
+            out.insert("url".to_string(), server_url.clone());
+            if url != server_url {
+                out.insert(ANTIGRAVITY_EXTRA_URL_KEY.to_string(), url.clone());
+            }
+        }
+        (Some(url), None) | (None, Some(url)) => {
+            out.insert("url".to_string(), url.clone());
+        }
+        (None, None) => {}
+    }
+
+    JsonValue::Object(out)
+}
+
+fn merge_opencode_env(
+    env: Option<&JsonValue>,
+    environment: Option<&JsonValue>,
+) -> Option<JsonValue> {
+    match (env, environment) {
+        (Some(JsonValue::Object(env)), Some(JsonValue::Object(environment))) => {
+            let mut merged = env.clone();
+            for (key, value) in environment {
+                if merged.get(key).is_some_and(|existing| existing != value) {
+                    log::warn!(
+                        "MCP env {} is set in both env and environment; using environment",
+                        key
+                    );
+                }
+                merged.insert(key.clone(), value.clone());
+            }
+            Some(JsonValue::Object(merged))
+        }
+        (Some(_), Some(environment)) => {
+            log::warn!("MCP server has both env and environment; using environment");
+            Some(environment.clone())
+        }
+        (env, environment) => environment.or(env).cloned(),
+    }
+}
+
+fn standard_to_opencode_config(config: &JsonValue) -> Result<JsonValue, String> {
+    let obj = config
+        .as_object()
+        .ok_or_else(|| "MCP server config must be an object".to_string())?;
+    let mut out = obj.clone();
+
+    if let Some(command) = obj.get("command") {
+        if let Some(command_str) = command.as_str() {
+            let mut command_list = Vec::new();
+            command_list.push(JsonValue::String(command_str.to_string()));
+            if let Some(args) = obj.get("args").and_then(|value| value.as_array()) {
+                for arg in args {
+                    if let Some(value) = arg.as_str() {
+                        command_list.push(JsonValue::String(value.to_string()));
+                    }
+                }
+            }
+            out.insert("command".to_string(), JsonValue::Array(command_list));
+        }
+        out.remove("args");
+    }
+
+    let env = out.remove("env");
+    if let Some(environment) = merge_opencode_env(env.as_ref(), out.get("environment")) {
+        out.insert("environment".to_string(), environment);
+    }
+
+    if !out.contains_key("type") {
+        if out.contains_key("url") {
+            out.insert("type".to_string(), JsonValue::String("remote".to_string()));
+        } else if out.contains_key("command") {
+            out.insert("type".to_string(), JsonValue::String("local".to_string()));
+        }
+    }
+
+    Ok(JsonValue::Object(out))
+}
+
+fn standard_to_antigravity_config(config: &JsonValue) -> Result<JsonValue, String> {
+    let obj = config
+        .as_object()
+        .ok_or_else(|| "MCP server config must be an object".to_string())?;
+    let mut out = JsonMap::new();
+
+    for (key, value) in obj {
+        if key != "serverUrl" && key != "url" && key != ANTIGRAVITY_EXTRA_URL_KEY {
+            out.insert(key.clone(), value.clone());
+        }
+    }
+
+    if let Some(url) = obj.get("serverUrl").or_else(|| obj.get("url")) {
+        out.insert("serverUrl".to_string(), url.clone());
+    }
+    if let Some(url) = obj.get(ANTIGRAVITY_EXTRA_URL_KEY) {
+        out.insert("url".to_string(), url.clone());
+    }
+
+    Ok(JsonValue::Object(out))
+}
+
+fn mcp_array_entry_id(server: &JsonValue) -> Option<(&'static str, String)> {
+    ["name", "id"].into_iter().find_map(|key| {
+        server
+            .get(key)
+            .and_then(|value| value.as_str())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(|value| (key, value.to_string()))
+    })
+}
+
+fn upsert_mcp_array_entry(
+    items: &mut Vec<JsonValue>,
+    id: String,
+    config: JsonValue,
+) -> Result<(), String> {
+    let JsonValue::Object(mut entry) = config else {
+        return Err("MCP server config must be an object".to_string());
+    };
+    let existing = items.iter().position(|item| {
+        mcp_array_entry_id(item).is_some_and(|(_, existing_id)| existing_id == id)
+    });
+    let key = existing
+        .and_then(|index| mcp_array_entry_id(&items[index]))
+        .map(|(key, _)| key)
+        .unwrap_or("name");
+    entry.insert(key.to_string(), JsonValue::String(id));
+    match existing {
+        Some(index) => items[index] = JsonValue::Object(entry),
+        None => items.push(JsonValue::Object(entry)),
+    }
+    Ok(())
+}
+
+fn project_mcp_config(project_root: &str) -> Result<McpSourceConfig, String> {
+    let trimmed = project_root.trim();
+    let root = match trimmed.strip_prefix("~/") {
+        Some(rest) => resolve_home()?.join(rest),
+        None => PathBuf::from(trimmed),
+    };
+    if trimmed.is_empty() || !root.is_absolute() {
+        return Err("Project root must be an absolute path".to_string());
+    }
+    if !root.is_dir() {
+        return Err(format!("{} is not a directory", root.display()));
+    }
+    let path = root.join(PROJECT_MCP_FILENAME);
+    Ok(McpSourceConfig {
+        id: PROJECT_MCP_SOURCE,
+        label: "Project",
+        format: "json",
+        kind: McpKind::ClaudeJson,
+        install_root: root,
+        primary_path: path.clone(),
+        read_paths: vec![path],
+    })
+}
+
+fn find_mcp_config(
+    home: &Path,
+    source_id: &str,
+    project_root: Option<&str>,
+) -> Result<McpSourceConfig, String> {
+    if source_id == PROJECT_MCP_SOURCE {
+        let project_root = project_root
+            .ok_or_else(|| "projectRoot is required for the project source".to_string())?;
+        return project_mcp_config(project_root);
+    }
+    mcp_source_configs(home)
+        .into_iter()
+        .find(|config| config.id == source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())
+}
+
+fn parse_mcp_kind(value: &str) -> Result<McpKind, String> {
+    match value.trim() {
+        "claudeJson" => Ok(McpKind::ClaudeJson),
+        "codexToml" => Ok(McpKind::CodexToml),
+        "antigravityJson" => Ok(McpKind::AntigravityJson),
+        "openCodeJson" => Ok(McpKind::OpenCodeJson),
+        other => Err(format!(
+            "Unknown MCP kind: {} (expected claudeJson, codexToml, antigravityJson or openCodeJson)",
+            other
+        )),
+    }
+}
+
+fn custom_mcp_config(path: &str, kind: &str) -> Result<McpSourceConfig, String> {
+    let kind = parse_mcp_kind(kind)?;
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        return Err("Path is required".to_string());
+    }
+    let path = match trimmed.strip_prefix("~/") {
+        Some(rest) => resolve_home()?.join(rest),
+        None => PathBuf::from(trimmed),
+    };
+    if !path.is_absolute() {
+        return Err("Path must be absolute".to_string());
+    }
+    Ok(McpSourceConfig {
+        id: "custom",
+        label: "Custom",
+        format: match kind {
+            McpKind::CodexToml => "toml",
+            _ => "json",
         },
-        SourceConfig {
-            id: "qoder-user",
-            label: "Qoder",
-            install_root: home.join(".qoder"),
-            root: home.join(".qoder").join("skills"),
-            core_files: qoder_files.clone(),
+        kind,
+        install_root: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        primary_path: path.clone(),
+        read_paths: vec![path],
+    })
+}
+
+fn read_mcp_servers(config: &McpSourceConfig, path: &Path) -> Result<Vec<McpServer>, String> {
+    let mut servers = Vec::new();
+    if !path.exists() {
+        return Ok(servers);
+    }
+
+    match config.kind {
+        McpKind::CodexToml => {
+            let value = load_toml_value(path)?;
+            if let Some(table) = value.get("mcp_servers").and_then(|item| item.as_table()) {
+                for (id, server) in table {
+                    servers.push(McpServer {
+                        id: id.to_string(),
+                        config: toml_to_json(server),
+                    });
+                }
+            }
+        }
+        McpKind::ClaudeJson => {
+            let value = load_json_value(path)?;
+            if let Some(servers_value) = value.get("mcpServers") {
+                if let Some(map) = servers_value.as_object() {
+                    for (id, server) in map {
+                        servers.push(McpServer {
+                            id: id.to_string(),
+                            config: server.clone(),
+                        });
+                    }
+                } else if let Some(items) = servers_value.as_array() {
+                    for server in items {
+                        if let Some((key, id)) = mcp_array_entry_id(server) {
+                            let mut config = server.clone();
+                            if let Some(obj) = config.as_object_mut() {
+                                obj.remove(key);
+                            }
+                            servers.push(McpServer { id, config });
+                        }
+                    }
+                }
+            }
+        }
+        McpKind::AntigravityJson => {
+            let value = load_json_value(path)?;
+            if let Some(servers_value) = value.get("mcpServers") {
+                if let Some(map) = servers_value.as_object() {
+                    for (id, server) in map {
+                        servers.push(McpServer {
+                            id: id.to_string(),
+                            config: antigravity_to_standard_config(server),
+                        });
+                    }
+                }
+            }
+        }
+        McpKind::OpenCodeJson => {
+            let value = load_json_value(path)?;
+            if let Some(servers_value) = value.get(opencode_servers_key(&value)) {
+                if let Some(map) = servers_value.as_object() {
+                    for (id, server) in map {
+                        servers.push(McpServer {
+                            id: id.to_string(),
+                            config: opencode_to_standard_config(server),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    for server in &mut servers {
+        normalize_message_url(&mut server.config);
+    }
+    servers.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(servers)
+}
+
+fn opencode_servers_key(value: &JsonValue) -> &'static str {
+    let populated = |key: &str| {
+        value
+            .get(key)
+            .and_then(|item| item.as_object())
+            .is_some_and(|map| !map.is_empty())
+    };
+    if populated("mcp") {
+        "mcp"
+    } else if populated("mcpServers")
+        || (value.get("mcp").is_none() && value.get("mcpServers").is_some())
+    {
+        "mcpServers"
+    } else {
+        "mcp"
+    }
+}
+
+fn normalize_message_url(config: &mut JsonValue) {
+    let Some(obj) = config.as_object_mut() else {
+        return;
+    };
+    for alias in SSE_MESSAGE_URL_ALIASES {
+        if let Some(value) = obj.remove(alias) {
+            if !obj.contains_key(SSE_MESSAGE_URL_KEY) {
+                obj.insert(SSE_MESSAGE_URL_KEY.to_string(), value);
+            }
+        }
+    }
+}
+
+fn executable_candidates(path: &Path) -> Vec<PathBuf> {
+    let mut candidates = vec![path.to_path_buf()];
+    if cfg!(windows) && path.extension().is_none() {
+        let extensions =
+            std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+        for extension in extensions.split(';').filter(|item| !item.is_empty()) {
+            candidates.push(path.with_extension(extension.trim_start_matches('.')));
+        }
+    }
+    candidates
+}
+
+fn find_command_on_path(command: &str) -> Option<PathBuf> {
+    let trimmed = command.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let direct = Path::new(trimmed);
+    if direct.is_absolute() || trimmed.contains('/') || trimmed.contains('\\') {
+        return executable_candidates(direct)
+            .into_iter()
+            .find(|path| path.is_file());
+    }
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .flat_map(|dir| executable_candidates(&dir.join(trimmed)))
+        .find(|path| path.is_file())
+}
+
+fn check_url_reachable(agent: &ureq::Agent, url: &str) -> Result<(), String> {
+    ensure_online()?;
+    match agent.head(url).call() {
+        Ok(_) | Err(ureq::Error::Status(_, _)) => Ok(()),
+        Err(err) => Err(format!("URL {} is unreachable: {}", url, err)),
+    }
+}
+
+fn server_env_file(config: &JsonValue, config_dir: &Path) -> Option<PathBuf> {
+    let value = config.get("envFile")?.as_str()?.trim();
+    if value.is_empty() {
+        return None;
+    }
+    if let Some(rest) = value.strip_prefix("~/") {
+        return resolve_home().ok().map(|home| home.join(rest));
+    }
+    Some(config_dir.join(value))
+}
+
+fn load_env_file(path: &Path) -> Result<Vec<(String, String)>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read env file {}: {}", path.display(), err))?;
+    let mut vars = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|quote| {
+                value
+                    .strip_prefix(*quote)
+                    .and_then(|inner| inner.strip_suffix(*quote))
+            })
+            .unwrap_or(value);
+        vars.push((key.trim().to_string(), value.to_string()));
+    }
+    Ok(vars)
+}
+
+const MASKED_ENV_VALUE: &str = "***";
+
+fn merge_env_file_values(config: &mut JsonValue, vars: &[(String, String)]) {
+    let Some(object) = config.as_object_mut() else {
+        return;
+    };
+    let env = object
+        .entry("env".to_string())
+        .or_insert_with(|| JsonValue::Object(JsonMap::new()));
+    let Some(env) = env.as_object_mut() else {
+        return;
+    };
+    for (key, _) in vars {
+        env.entry(key.clone())
+            .or_insert_with(|| JsonValue::String(MASKED_ENV_VALUE.to_string()));
+    }
+}
+
+fn strip_env_file_values(config: &mut JsonValue, config_dir: &Path) {
+    let Some(path) = server_env_file(config, config_dir) else {
+        return;
+    };
+    let Ok(vars) = load_env_file(&path) else {
+        return;
+    };
+    let Some(object) = config.as_object_mut() else {
+        return;
+    };
+    let Some(env) = object.get_mut("env").and_then(|item| item.as_object_mut()) else {
+        return;
+    };
+    for (key, _) in &vars {
+        if env.get(key).and_then(|item| item.as_str()) == Some(MASKED_ENV_VALUE) {
+            env.remove(key);
+        }
+    }
+    if env.is_empty() {
+        object.remove("env");
+    }
+}
+
+fn env_file_keys(
+    servers: &mut [McpServer],
+    config_dir: &Path,
+    warnings: &mut Vec<String>,
+) -> HashMap<String, Vec<String>> {
+    let mut keys = HashMap::new();
+    for server in servers {
+        let Some(path) = server_env_file(&server.config, config_dir) else {
+            continue;
+        };
+        match load_env_file(&path) {
+            Ok(vars) => {
+                merge_env_file_values(&mut server.config, &vars);
+                keys.insert(
+                    server.id.clone(),
+                    vars.into_iter().map(|(key, _)| key).collect(),
+                );
+            }
+            Err(err) => warnings.push(format!("{}: {}", server.id, err)),
+        }
+    }
+    keys
+}
+
+fn mcp_server_health(
+    agent: &ureq::Agent,
+    server: &McpServer,
+    input_ids: &HashSet<String>,
+    config_dir: &Path,
+) -> Vec<McpHealthIssue> {
+    let mut issues = Vec::new();
+    let mut push = |level: &str, message: String| {
+        issues.push(McpHealthIssue {
+            server_id: server.id.clone(),
+            level: level.to_string(),
+            message,
+        });
+    };
+
+    let Some(obj) = server.config.as_object() else {
+        push("error", "Server config must be an object".to_string());
+        return issues;
+    };
+
+    let command = obj.get("command").and_then(|item| item.as_str());
+    let url = obj.get("url").and_then(|item| item.as_str());
+    match (command, url) {
+        (None, None) => push("error", "Missing both command and url".to_string()),
+        (Some(command), _) => {
+            if command.trim().is_empty() {
+                push("error", "Command is empty".to_string());
+            } else if find_command_on_path(command).is_none() {
+                push("warning", format!("Command {} not found on PATH", command));
+            }
+        }
+        (None, Some(url)) => {
+            if Url::parse(url).is_err() {
+                push("error", format!("Invalid url {}", url));
+            } else if offline_mode() {
+                push(
+                    "info",
+                    "Skipped url reachability check in offline mode".to_string(),
+                );
+            } else if let Err(err) = check_url_reachable(agent, url) {
+                push("warning", err);
+            }
+        }
+    }
+
+    let mut references = Vec::new();
+    collect_input_references(&server.config, &mut references);
+    for reference in references {
+        if !input_ids.contains(&reference) {
+            push(
+                "warning",
+                format!("References undefined input ${{input:{}}}", reference),
+            );
+        }
+    }
+
+    if let Some(path) = server_env_file(&server.config, config_dir) {
+        if let Err(err) = load_env_file(&path) {
+            push("error", err);
+        }
+    }
+
+    if let Some(env) = obj.get("env").and_then(|item| item.as_object()) {
+        let mut seen: HashMap<String, &str> = HashMap::new();
+        for key in env.keys() {
+            let normalized = key.trim().to_ascii_uppercase();
+            if let Some(previous) = seen.get(&normalized) {
+                push(
+                    "warning",
+                    format!("Env keys {} and {} look like duplicates", previous, key),
+                );
+            } else {
+                seen.insert(normalized, key);
+            }
+        }
+    }
+
+    issues
+}
+
+fn strip_antigravity_extras(config: &mut JsonValue) {
+    if let Some(obj) = config.as_object_mut() {
+        obj.remove(ANTIGRAVITY_EXTRA_URL_KEY);
+    }
+}
+
+fn mcp_config_for_kind(kind: &McpKind, config: &JsonValue) -> Result<JsonValue, String> {
+    let mut config = config.clone();
+    if !matches!(kind, McpKind::AntigravityJson) {
+        strip_antigravity_extras(&mut config);
+    }
+    match kind {
+        McpKind::CodexToml => Ok(toml_to_json(&json_to_toml(&config)?)),
+        McpKind::ClaudeJson => Ok(config),
+        McpKind::AntigravityJson => standard_to_antigravity_config(&config),
+        McpKind::OpenCodeJson => standard_to_opencode_config(&config),
+    }
+}
+
+fn upsert_mcp_servers(
+    config: &McpSourceConfig,
+    mut servers: HashMap<String, JsonValue>,
+) -> Result<(), String> {
+    let config_dir = config
+        .primary_path
+        .parent()
+        .unwrap_or(&config.install_root)
+        .to_path_buf();
+    for config_value in servers.values_mut() {
+        strip_env_file_values(config_value, &config_dir);
+        if !matches!(config.kind, McpKind::AntigravityJson) {
+            strip_antigravity_extras(config_value);
+        }
+    }
+    match config.kind {
+        McpKind::CodexToml => {
+            let mut value = load_toml_value(&config.primary_path)?;
+            let table = value
+                .as_table_mut()
+                .ok_or_else(|| "Invalid config format".to_string())?;
+            let mcp_entry = table
+                .entry("mcp_servers".to_string())
+                .or_insert_with(|| TomlValue::Table(TomlMap::new()));
+            let mcp_table = mcp_entry
+                .as_table_mut()
+                .ok_or_else(|| "Invalid mcp_servers format".to_string())?;
+
+            for (id, config_value) in servers {
+                let toml_value = json_to_toml(&config_value)?;
+                mcp_table.insert(id, toml_value);
+            }
+
+            save_toml_value(&config.primary_path, &value)?;
+        }
+        McpKind::ClaudeJson => {
+            let mut value = load_json_value(&config.primary_path)?;
+            let root = value
+                .as_object_mut()
+                .ok_or_else(|| "Invalid JSON format".to_string())?;
+            let servers_value = root
+                .entry("mcpServers".to_string())
+                .or_insert_with(|| JsonValue::Object(JsonMap::new()));
+            if let Some(items) = servers_value.as_array_mut() {
+                for (id, config_value) in servers {
+                    upsert_mcp_array_entry(items, id, config_value)?;
+                }
+                save_json_value(&config.primary_path, &value)?;
+                return Ok(());
+            }
+            let servers_map = servers_value
+                .as_object_mut()
+                .ok_or_else(|| "Invalid mcpServers format".to_string())?;
+
+            for (id, config_value) in servers {
+                servers_map.insert(id, config_value);
+            }
+
+            save_json_value(&config.primary_path, &value)?;
+        }
+        McpKind::AntigravityJson => {
+            let mut value = load_json_value(&config.primary_path)?;
+            let root = value
+                .as_object_mut()
+                .ok_or_else(|| "Invalid JSON format".to_string())?;
+            let servers_value = root
+                .entry("mcpServers".to_string())
+                .or_insert_with(|| JsonValue::Object(JsonMap::new()));
+            let servers_map = servers_value
+                .as_object_mut()
+                .ok_or_else(|| "Invalid mcpServers format".to_string())?;
+
+            for (id, config_value) in servers {
+                let converted = mcp_config_for_kind(&config.kind, &config_value)?;
+                servers_map.insert(id, converted);
+            }
+
+            save_json_value(&config.primary_path, &value)?;
+        }
+        McpKind::OpenCodeJson => {
+            let mut value = load_json_value(&config.primary_path)?;
+            let key = opencode_servers_key(&value);
+            let root = value
+                .as_object_mut()
+                .ok_or_else(|| "Invalid JSON format".to_string())?;
+            let mcp_value = root
+                .entry(key.to_string())
+                .or_insert_with(|| JsonValue::Object(JsonMap::new()));
+            let mcp_map = mcp_value
+                .as_object_mut()
+                .ok_or_else(|| format!("Invalid {} format", key))?;
+
+            for (id, config_value) in servers {
+                let converted = mcp_config_for_kind(&config.kind, &config_value)?;
+                mcp_map.insert(id, converted);
+            }
+
+            save_json_value(&config.primary_path, &value)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn delete_mcp_server_for_source(config: &McpSourceConfig, server_id: &str) -> Result<(), String> {
+    match config.kind {
+        McpKind::CodexToml => {
+            let mut value = load_toml_value(&config.primary_path)?;
+            let table = value
+                .as_table_mut()
+                .ok_or_else(|| "Invalid config format".to_string())?;
+            if let Some(mcp_table) = table
+                .get_mut("mcp_servers")
+                .and_then(|item| item.as_table_mut())
+            {
+                if mcp_table.remove(server_id).is_none() {
+                    return Err("MCP server not found".to_string());
+                }
+            } else {
+                return Err("No MCP servers configured".to_string());
+            }
+            save_toml_value(&config.primary_path, &value)?;
+        }
+        McpKind::ClaudeJson | McpKind::AntigravityJson => {
+            let mut value = load_json_value(&config.primary_path)?;
+            let root = value
+                .as_object_mut()
+                .ok_or_else(|| "Invalid JSON format".to_string())?;
+            let servers_value = root
+                .get_mut("mcpServers")
+                .ok_or_else(|| "No mcpServers configured".to_string())?;
+
+            if let Some(items) = servers_value.as_array_mut() {
+                let before = items.len();
+                items.retain(|item| {
+                    mcp_array_entry_id(item).map(|(_, id)| id).as_deref() != Some(server_id)
+                });
+                if items.len() == before {
+                    return Err("MCP server not found".to_string());
+                }
+            } else {
+                let servers_map = servers_value
+                    .as_object_mut()
+                    .ok_or_else(|| "Invalid mcpServers format".to_string())?;
+                if servers_map.remove(server_id).is_none() {
+                    return Err("MCP server not found".to_string());
+                }
+            }
+            save_json_value(&config.primary_path, &value)?;
+        }
+        McpKind::OpenCodeJson => {
+            let mut value = load_json_value(&config.primary_path)?;
+            let key = opencode_servers_key(&value);
+            let root = value
+                .as_object_mut()
+                .ok_or_else(|| "Invalid JSON format".to_string())?;
+            let mcp_value = root
+                .get_mut(key)
+                .and_then(|item| item.as_object_mut())
+                .ok_or_else(|| format!("No {} configured", key))?;
+
+            if mcp_value.remove(server_id).is_none() {
+                return Err("MCP server not found".to_string());
+            }
+            save_json_value(&config.primary_path, &value)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn skill_tree_stats(path: &Path) -> (u64, u64) {
+    let mut files = Vec::new();
+    if path.is_dir() {
+        let _ = collect_skill_files(path, "", &mut files);
+    } else {
+        files.push((String::new(), path.to_path_buf()));
+    }
+    let mut bytes = 0;
+    let mut modified = 0;
+    for (_, file) in files {
+        let Ok(metadata) = fs::metadata(&file) else {
+            continue;
+        };
+        bytes += metadata.len();
+        if let Some(secs) = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs())
+        {
+            modified = modified.max(secs);
+        }
+    }
+    (bytes, modified)
+}
+
+fn sort_skills(skills: &mut [SkillItem], sort_by: &str, descending: bool) -> Result<(), String> {
+    match sort_by {
+        "name" => {}
+        "modified" | "size" => {
+            let keys: HashMap<String, u64> = skills
+                .iter()
+                .map(|skill| {
+                    let (bytes, modified) = skill_tree_stats(Path::new(&skill.path));
+                    let key = if sort_by == "size" { bytes } else { modified };
+                    (skill.id.clone(), key)
+                })
+                .collect();
+            skills.sort_by_key(|skill| keys.get(&skill.id).copied().unwrap_or(0));
+        }
+        other => return Err(format!("Unknown sort key {}", other)),
+    }
+    if descending {
+        skills.reverse();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn list_skills(payload: Option<ListSkillsInput>) -> Result<Vec<SkillSource>, String> {
+    let (sort_by, descending) = payload
+        .map(|payload| (payload.sort_by, payload.descending))
+        .unwrap_or_default();
+    let sort_by = sort_by.as_deref().map(str::trim).unwrap_or("name");
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let settings = read_app_settings(&home);
+    let mut response = Vec::new();
+
+    for source in sources {
+        if !source_enabled(&settings, source.id) {
+            continue;
+        }
+        let installed = source.install_root.is_dir();
+        let root_exists = source.root.is_dir();
+        let mut skills = if root_exists {
+            read_skills(&source)
+        } else {
+            vec![]
+        };
+        sort_skills(&mut skills, sort_by, descending)?;
+        response.push(SkillSource {
+            id: source.id.to_string(),
+            label: source.label.to_string(),
+            root: source.root.display().to_string(),
+            installed,
+            exists: root_exists,
+            core_files: source
+                .core_files
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            skills,
+        });
+    }
+
+    Ok(response)
+}
+
+#[tauri::command]
+fn list_skill_tree(payload: SkillTreeInput) -> Result<SkillTreeNode, String> {
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let skill_dir = source.root.join(&payload.skill_id);
+    if !skill_dir.exists() {
+        return Err("Skill not found".to_string());
+    }
+
+    let root_canon =
+        fs::canonicalize(&source.root).map_err(|err| format!("Failed to resolve root: {}", err))?;
+    let skill_canon =
+        fs::canonicalize(&skill_dir).map_err(|err| format!("Failed to resolve skill: {}", err))?;
+    if !skill_canon.starts_with(&root_canon) {
+        return Err("Refusing to read outside agent root".to_string());
+    }
+
+    build_skill_tree(&skill_dir)
+}
+
+struct RemoteCore {
+    content: String,
+    core_file_name: String,
+    github: Option<(GithubLocation, Vec<String>)>,
+    archive: Option<SkillArchive>,
+}
+
+struct SkillArchive {
+    bytes: Vec<u8>,
+    prefix: String,
+}
+
+fn is_zip_url(url: &str) -> bool {
+    Url::parse(url.trim())
+        .map(|parsed| parsed.path().to_ascii_lowercase().ends_with(".zip"))
+        .unwrap_or(false)
+}
+
+fn is_zip_content_type(content_type: &str) -> bool {
+    matches!(
+        content_type.trim().to_ascii_lowercase().as_str(),
+        "application/zip" | "application/x-zip-compressed" | "application/x-zip"
+    )
+}
+
+fn is_zip_source(url: &str) -> bool {
+    if is_zip_url(url) {
+        return true;
+    }
+    if parse_github_location(url).is_ok() || offline_mode() {
+        return false;
+    }
+    http_agent()
+        .head(url.trim())
+        .call()
+        .is_ok_and(|response| is_zip_content_type(response.content_type()))
+}
+
+fn read_capped<R: Read>(reader: R, name: &str, limit: u64) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    reader
+        .take(limit + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|err| format!("Failed to read {}: {}", name, err))?;
+    if bytes.len() as u64 > limit {
+        return Err(format!("{} expands beyond {} bytes", name, limit));
+    }
+    Ok(bytes)
+}
+
+fn fetch_skill_archive(url: &str, core_files: &[&str]) -> Result<RemoteCore, String> {
+    ensure_online()?;
+    log::debug!("archive request {}", url.trim());
+    let response = http_agent()
+        .get(url.trim())
+        .call()
+        .map_err(|err| format!("Failed to download {}: {}", url.trim(), err))?;
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_SKILL_ARCHIVE_BYTES + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|err| format!("Failed to read archive: {}", err))?;
+    if bytes.len() as u64 > MAX_SKILL_ARCHIVE_BYTES {
+        return Err(format!("Archive exceeds {} bytes", MAX_SKILL_ARCHIVE_BYTES));
+    }
+
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes.as_slice()))
+        .map_err(|err| format!("Invalid archive: {}", err))?;
+    let mut names = Vec::new();
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index(index)
+            .map_err(|err| format!("Invalid archive entry: {}", err))?;
+        if entry.enclosed_name().is_none() {
+            return Err(format!("Archive contains unsafe path {}", entry.name()));
+        }
+        if !entry.is_dir() {
+            names.push(entry.name().to_string());
+        }
+    }
+
+    let top_dirs: HashSet<&str> = names
+        .iter()
+        .filter_map(|name| name.split_once('/').map(|(dir, _)| dir))
+        .collect();
+    let prefix = match top_dirs.iter().next() {
+        Some(dir) if top_dirs.len() == 1 && names.iter().all(|name| name.contains('/')) => {
+            format!("{}/", dir)
+        }
+        _ => String::new(),
+    };
+    let (entry_name, core_file_name) = core_files
+        .iter()
+        .find_map(|core_file| {
+            names.iter().find_map(|name| {
+                let relative = name.strip_prefix(&prefix)?;
+                relative
+                    .eq_ignore_ascii_case(core_file)
+                    .then(|| (name.clone(), relative.to_string()))
+            })
+        })
+        .ok_or_else(|| "Archive has no core file at its top level".to_string())?;
+    let content = String::from_utf8(read_archive_entry(&mut archive, &entry_name)?)
+        .map_err(|err| format!("{} is not UTF-8: {}", core_file_name, err))?;
+
+    Ok(RemoteCore {
+        content,
+        core_file_name,
+        github: None,
+        archive: Some(SkillArchive { bytes, prefix }),
+    })
+}
+
+fn extract_skill_archive(archive: &SkillArchive, dest: &Path) -> Result<DownloadStats, String> {
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive.bytes.as_slice()))
+        .map_err(|err| format!("Invalid archive: {}", err))?;
+    let mut stats = DownloadStats::default();
+    for index in 0..zip.len() {
+        let entry = zip
+            .by_index(index)
+            .map_err(|err| format!("Invalid archive entry: {}", err))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let Some(relative) = name.strip_prefix(&archive.prefix) else {
+            continue;
+        };
+        let relative = safe_relative_path(relative)
+            .ok_or_else(|| format!("Archive contains unsafe path {}", name))?;
+        let remaining = MAX_ARCHIVE_EXTRACTED_BYTES.saturating_sub(stats.bytes);
+        if remaining == 0 {
+            return Err(format!(
+                "Archive expands beyond {} bytes",
+                MAX_ARCHIVE_EXTRACTED_BYTES
+            ));
+        }
+        let bytes = read_capped(entry, &name, MAX_ARCHIVE_ENTRY_BYTES.min(remaining))?;
+        write_bytes_to_path(&bytes, &dest.join(relative))?;
+        stats.files += 1;
+        stats.bytes += bytes.len() as u64;
+    }
+    Ok(stats)
+}
+
+fn fetch_remote_core(url: &str, core_files: &[&str]) -> Result<RemoteCore, String> {
+    if is_zip_source(url) {
+        return fetch_skill_archive(url, core_files);
+    }
+    let github_location = parse_github_location(url).ok();
+    let github_agent = github_location.as_ref().map(|_| http_agent());
+    let github_branches = match (github_location.as_ref(), github_agent.as_ref()) {
+        (Some(location), Some(agent)) => Some(github_branch_candidates(agent, location)),
+        _ => None,
+    };
+    let mut content = None;
+    let mut core_file_name = None;
+    let mut last_error = None;
+    let mut selected_branch = None;
+
+    'core_lookup: for file_name in core_files {
+        if let (Some(location), Some(agent), Some(branches)) = (
+            github_location.as_ref(),
+            github_agent.as_ref(),
+            github_branches.as_ref(),
+        ) {
+            for branch in branches {
+                let path = github_file_path(location, file_name);
+                match fetch_github_file_content(
+                    agent,
+                    &location.owner,
+                    &location.repo,
+                    &path,
+                    branch,
+                ) {
+                    Ok(bytes) => match String::from_utf8(bytes) {
+                        Ok(body) => {
+                            content = Some(body);
+                            core_file_name = Some(file_name.to_string());
+                            selected_branch = Some(branch.to_string());
+                            break 'core_lookup;
+                        }
+                        Err(err) => {
+                            last_error = Some(format!("GitHub file is not UTF-8: {}", err));
+                        }
+                    },
+                    Err(err) => {
+                        last_error = Some(err);
+                    }
+                }
+            }
+        } else {
+            let candidates = parse_skill_urls(url, file_name)?;
+            match fetch_skill_content(candidates, file_name) {
+                Ok(body) => {
+                    content = Some(body);
+                    core_file_name = Some(file_name.to_string());
+                    break;
+                }
+                Err(err) => {
+                    last_error = Some(err);
+                }
+            }
+        }
+    }
+
+    let content = content.ok_or_else(|| match &github_branches {
+        Some(branches) => branches_tried_error(&core_files.join(" or "), branches, last_error),
+        None => last_error.unwrap_or_else(|| "Unable to download skill file".to_string()),
+    })?;
+    let core_file_name = core_file_name.ok_or_else(|| "Missing core file".to_string())?;
+    let github = match (github_location, github_branches) {
+        (Some(location), Some(mut branches)) => {
+            if let Some(branch) = selected_branch {
+                branches.retain(|item| item != &branch);
+                branches.insert(0, branch);
+            }
+            Some((location, branches))
+        }
+        _ => None,
+    };
+
+    Ok(RemoteCore {
+        content,
+        core_file_name,
+        github,
+        archive: None,
+    })
+}
+
+fn remote_skill_name(url: &str, remote: &RemoteCore) -> String {
+    let metadata = if remote.core_file_name.to_ascii_lowercase().ends_with(".md") {
+        parse_frontmatter(&remote.content).0
+    } else {
+        HashMap::new()
+    };
+    metadata
+        .get("name")
+        .cloned()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| {
+            let fallback = fallback_name_from_url(url, &remote.core_file_name);
+            if fallback.to_ascii_lowercase().ends_with(".zip") {
+                fallback[..fallback.len() - 4].to_string()
+            } else {
+                fallback
+            }
+        })
+}
+
+fn unique_skill_dir(root: &Path, base_slug: &str) -> PathBuf {
+    let mut skill_dir = root.join(base_slug);
+    let mut suffix = 1;
+    while skill_dir.exists() {
+        skill_dir = root.join(format!("{}-{}", base_slug, suffix));
+        suffix += 1;
+    }
+    skill_dir
+}
+
+fn write_remote_skill(
+    remote: RemoteCore,
+    url: &str,
+    skill_dir: &Path,
+) -> Result<DownloadStats, String> {
+    fs::create_dir_all(skill_dir)
+        .map_err(|err| format!("Failed to create {}: {}", skill_dir.display(), err))?;
+    let mut stats = DownloadStats::default();
+    if let Some((location, branches)) = remote.github {
+        let agent = http_agent();
+        let mut last_download_error = None;
+        let mut downloaded = None;
+        for branch in branches {
+            match download_github_directory(
+                &agent,
+                &location,
+                &branch,
+                &remote.core_file_name,
+                skill_dir,
+            ) {
+                Ok(stats) => {
+                    downloaded = Some(stats);
+                    break;
+                }
+                Err(err) => {
+                    last_download_error = Some(err);
+                }
+            }
+        }
+        stats = downloaded.ok_or_else(|| {
+            last_download_error.unwrap_or_else(|| "Unable to download GitHub directory".to_string())
+        })?;
+    } else if let Some(archive) = &remote.archive {
+        stats = extract_skill_archive(archive, skill_dir)?;
+    } else {
+        stats.files = 1;
+        stats.bytes = remote.content.len() as u64;
+    }
+
+    write_skill_source(
+        skill_dir,
+        &SkillSourceRecord {
+            url: url.trim().to_string(),
+            branch: None,
+            core_hash: Some(hash_bytes(remote.content.as_bytes())),
         },
-        SourceConfig {
-            id: "codebuddy-user",
-            label: "CodeBuddy",
-            install_root: home.join(".codebuddy"),
-            root: home.join(".codebuddy").join("skills"),
-            core_files: codebuddy_files.clone(),
+    )?;
+    let core_path = find_core_file(skill_dir, &[remote.core_file_name.as_str()])
+        .map(|(path, _)| path)
+        .unwrap_or_else(|| skill_dir.join(&remote.core_file_name));
+    fs::write(&core_path, remote.content).map_err(|err| write_error("File", &core_path, err))?;
+    Ok(stats)
+}
+
+#[tauri::command]
+fn preview_remote_skill(payload: PreviewRemoteSkillInput) -> Result<RemoteSkillPreview, String> {
+    ensure_online()?;
+    let _guard = override_github_token(payload.token.as_deref());
+    let home = resolve_home()?;
+    let core_files = match payload.source_id.as_deref() {
+        Some(source_id) => {
+            source_configs(&home)
+                .into_iter()
+                .find(|source| source.id == source_id)
+                .ok_or_else(|| "Unknown skill source".to_string())?
+                .core_files
+        }
+        None => vec!["SKILL.md"],
+    };
+
+    let remote = fetch_remote_core(&payload.url, &core_files)?;
+    let (metadata, body) = if remote.core_file_name.to_ascii_lowercase().ends_with(".md") {
+        parse_frontmatter(&remote.content)
+    } else {
+        (HashMap::new(), String::new())
+    };
+    let name = metadata
+        .get("name")
+        .cloned()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| fallback_name_from_url(&payload.url, &remote.core_file_name));
+    let description = skill_description(&metadata, &body).0;
+
+    Ok(RemoteSkillPreview {
+        name,
+        description,
+        core_file: remote.core_file_name,
+        metadata,
+    })
+}
+
+fn remote_skill_entry(
+    agent: &ureq::Agent,
+    location: &GithubLocation,
+    branch: &str,
+    dir: &GithubContentEntry,
+    base_url: &str,
+    core_files: &[&str],
+) -> RemoteSkillEntry {
+    let mut entry = RemoteSkillEntry {
+        dir: dir.name.clone(),
+        url: format!("{}/{}", base_url, dir.name),
+        core_file: None,
+        name: None,
+        description: None,
+    };
+    let Ok(children) =
+        fetch_github_contents(agent, &location.owner, &location.repo, &dir.path, branch)
+    else {
+        return entry;
+    };
+    let Some(core) = core_files.iter().find_map(|core_file| {
+        children
+            .iter()
+            .find(|child| child.item_type == "file" && child.name.eq_ignore_ascii_case(core_file))
+    }) else {
+        return entry;
+    };
+    entry.core_file = Some(core.name.clone());
+    if !core.name.to_ascii_lowercase().ends_with(".md") {
+        return entry;
+    }
+    if let Ok(bytes) =
+        fetch_github_file_content(agent, &location.owner, &location.repo, &core.path, branch)
+    {
+        let (metadata, body) = parse_frontmatter(&String::from_utf8_lossy(&bytes));
+        entry.name = metadata
+            .get("name")
+            .cloned()
+            .filter(|value| !value.trim().is_empty());
+        entry.description =
+            Some(skill_description(&metadata, &body).0).filter(|value| !value.is_empty());
+    }
+    entry
+}
+
+#[tauri::command]
+fn list_remote_skills(payload: ListRemoteSkillsInput) -> Result<Vec<RemoteSkillEntry>, String> {
+    static REMOTE_SKILLS: OnceLock<Mutex<RemoteSkillsCache>> = OnceLock::new();
+    ensure_online()?;
+    let _guard = override_github_token(payload.token.as_deref());
+    let location = parse_github_location(&payload.url)?;
+    let cache = REMOTE_SKILLS.get_or_init(|| Mutex::new(HashMap::new()));
+    let key = format!(
+        "{}/{}@{}:{}",
+        location.owner,
+        location.repo,
+        location.branch.as_deref().unwrap_or_default(),
+        location.path
+    )
+    .to_ascii_lowercase();
+    if let Some(entries) = cache.lock().ok().and_then(|cache| {
+        cache
+            .get(&key)
+            .filter(|(fetched_at, _)| unix_timestamp() < fetched_at + REMOTE_SKILLS_CACHE_SECS)
+            .map(|(_, entries)| entries.clone())
+    }) {
+        return Ok(entries);
+    }
+
+    let home = resolve_home()?;
+    let mut core_files: Vec<&str> = Vec::new();
+    for source in source_configs(&home) {
+        for name in source.core_files {
+            if !core_files.contains(&name) {
+                core_files.push(name);
+            }
+        }
+    }
+
+    let agent = http_agent();
+    let branches = github_branch_candidates(&agent, &location);
+    let mut last_error = None;
+    let mut listing = None;
+    for branch in &branches {
+        match fetch_github_contents(
+            &agent,
+            &location.owner,
+            &location.repo,
+            &location.path,
+            branch,
+        ) {
+            Ok(items) => {
+                listing = Some((branch, items));
+                break;
+            }
+            Err(err) => last_error = Some(err),
+        }
+    }
+    let (branch, items) = listing
+        .ok_or_else(|| branches_tried_error("repository contents", &branches, last_error))?;
+
+    let base_url = github_tree_url(&payload.url, Some(branch))?;
+    let mut entries: Vec<RemoteSkillEntry> = items
+        .iter()
+        .filter(|item| item.item_type == "dir" && !item.name.starts_with('.'))
+        .map(|dir| remote_skill_entry(&agent, &location, branch, dir, &base_url, &core_files))
+        .collect();
+    entries.sort_by_key(|entry| entry.dir.to_lowercase());
+
+    if let Ok(mut cache) = cache.lock() {
+        cache.insert(key, (unix_timestamp(), entries.clone()));
+    }
+    Ok(entries)
+}
+
+#[tauri::command]
+fn preview_install_slug(payload: PreviewInstallSlugInput) -> Result<InstallSlugPreview, String> {
+    let _guard = override_github_token(payload.token.as_deref());
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    preview_install_slug_for(source, &payload.url, payload.on_existing.as_deref())
+}
+
+fn preview_install_slug_for(
+    source: &SourceConfig,
+    url: &str,
+    on_existing: Option<&str>,
+) -> Result<InstallSlugPreview, String> {
+    if updates_existing_skill(on_existing)? {
+        if let Some(existing) = installed_skill_for_url(source, url) {
+            return Ok(InstallSlugPreview {
+                slug: existing.id,
+                updates_existing: true,
+            });
+        }
+    }
+
+    ensure_online()?;
+    let remote = fetch_remote_core(url, &source.core_files)?;
+    let skill_dir = unique_skill_dir(&source.root, &slugify(&remote_skill_name(url, &remote)));
+    let slug = skill_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| "Failed to resolve install slug".to_string())?;
+    Ok(InstallSlugPreview {
+        slug,
+        updates_existing: false,
+    })
+}
+
+fn updates_existing_skill(on_existing: Option<&str>) -> Result<bool, String> {
+    match on_existing.unwrap_or("update") {
+        "update" => Ok(true),
+        "suffix" => Ok(false),
+        other => Err(format!("Unknown onExisting mode: {}", other)),
+    }
+}
+
+fn installed_skill_for_url(source: &SourceConfig, url: &str) -> Option<SkillItem> {
+    read_skills(source)
+        .into_iter()
+        .find(|skill| skill.source_url.as_deref().map(str::trim) == Some(url.trim()))
+}
+
+#[tauri::command]
+fn install_skill_from_url(payload: InstallSkillInput) -> Result<InstallResult, String> {
+    let source_id = payload.source_id.clone();
+    let url = payload.url.clone();
+    let result = install_skill(payload);
+    let target = result
+        .as_ref()
+        .map(|installed| installed.skill.id.as_str())
+        .unwrap_or_default();
+    record_history("install_skill", &source_id, target, Some(&url), &result);
+    result
+}
+
+fn install_skill(payload: InstallSkillInput) -> Result<InstallResult, String> {
+    ensure_online()?;
+    let _guard = override_github_token(payload.token.as_deref());
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    if updates_existing_skill(payload.on_existing.as_deref())? {
+        if let Some(existing) = installed_skill_for_url(source, &payload.url) {
+            let skill_dir = resolve_skill_dir(source, &existing.id, "sync")?;
+            let (skill, stats) = if payload.only_core {
+                let skill = refresh_skill_core(source, &skill_dir, false)?;
+                let bytes = fs::metadata(&skill.core_file_path)
+                    .map(|metadata| metadata.len())
+                    .unwrap_or_default();
+                (skill, DownloadStats { files: 1, bytes })
+            } else {
+                sync_skill_dir(source, &skill_dir, &payload.url, None, false)?
+            };
+            return Ok(InstallResult {
+                skill,
+                created: false,
+                files_downloaded: stats.files,
+                bytes_downloaded: stats.bytes,
+            });
+        }
+    }
+
+    fs::create_dir_all(&source.root)
+        .map_err(|err| format!("Failed to create {}: {}", source.root.display(), err))?;
+
+    let mut remote = fetch_remote_core(&payload.url, &source.core_files)?;
+    if payload.only_core {
+        remote.github = None;
+        remote.archive = None;
+    }
+    let skill_dir = unique_skill_dir(
+        &source.root,
+        &slugify(&remote_skill_name(&payload.url, &remote)),
+    );
+    let stats = write_remote_skill(remote, &payload.url, &skill_dir)?;
+    let (core_path, core_file_name) = skill_core_file(&skill_dir, source)?;
+
+    let skill = load_skill(&skill_dir, &core_path, &core_file_name, source)?;
+    Ok(InstallResult {
+        skill,
+        created: true,
+        files_downloaded: stats.files,
+        bytes_downloaded: stats.bytes,
+    })
+}
+
+#[tauri::command]
+fn install_skill_to_sources(
+    payload: InstallToSourcesInput,
+) -> Result<HashMap<String, SourceInstallResult>, String> {
+    ensure_online()?;
+    let _guard = override_github_token(payload.token.as_deref());
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source_ids = expand_source_ids(&payload.source_ids, &sources);
+    let targets: Vec<(&String, Option<&SourceConfig>)> = source_ids
+        .iter()
+        .map(|source_id| {
+            (
+                source_id,
+                sources.iter().find(|source| source.id == source_id),
+            )
+        })
+        .collect();
+
+    let mut core_files: Vec<&str> = Vec::new();
+    for source in targets.iter().filter_map(|(_, source)| *source) {
+        for name in &source.core_files {
+            if !core_files.contains(name) {
+                core_files.push(name);
+            }
+        }
+    }
+    if core_files.is_empty() {
+        return Err("No known skill sources selected".to_string());
+    }
+
+    let remote = fetch_remote_core(&payload.url, &core_files)?;
+    let core_file_name = remote.core_file_name.clone();
+    let base_slug = slugify(&remote_skill_name(&payload.url, &remote));
+    let staging = std::env::temp_dir().join(format!(
+        "ananke-install-{}-{}",
+        std::process::id(),
+        unix_timestamp()
+    ));
+    let staged = write_remote_skill(remote, &payload.url, &staging);
+    if let Err(err) = staged {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(err);
+    }
+
+    let mut results = HashMap::new();
+    for (source_id, source) in targets {
+        let result = source
+            .ok_or_else(|| "Unknown skill source".to_string())
+            .and_then(|source| {
+                if !source
+                    .core_files
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case(&core_file_name))
+                {
+                    return Err(format!(
+                        "Source does not use {} as a core file",
+                        core_file_name
+                    ));
+                }
+                fs::create_dir_all(&source.root).map_err(|err| {
+                    format!("Failed to create {}: {}", source.root.display(), err)
+                })?;
+                let skill_dir = unique_skill_dir(&source.root, &base_slug);
+                copy_dir_recursive(&staging, &skill_dir)?;
+                let (core_path, core_name) = skill_core_file(&skill_dir, source)?;
+                load_skill(&skill_dir, &core_path, &core_name, source)
+            });
+        let target = result
+            .as_ref()
+            .map(|skill| skill.id.as_str())
+            .unwrap_or(&base_slug);
+        record_history(
+            "install_skill",
+            source_id,
+            target,
+            Some(&payload.url),
+            &result,
+        );
+        results.insert(
+            source_id.clone(),
+            match result {
+                Ok(skill) => SourceInstallResult {
+                    skill: Some(skill),
+                    error: None,
+                },
+                Err(err) => SourceInstallResult {
+                    skill: None,
+                    error: Some(err),
+                },
+            },
+        );
+    }
+
+    let _ = fs::remove_dir_all(&staging);
+    Ok(results)
+}
+
+fn expand_source_ids(ids: &[String], sources: &[SourceConfig]) -> Vec<String> {
+    let mut expanded: Vec<String> = Vec::new();
+    for id in ids {
+        let candidates: Vec<String> = if id == ALL_SOURCES {
+            sources
+                .iter()
+                .filter(|source| source.install_root.is_dir())
+                .map(|source| source.id.to_string())
+                .collect()
+        } else {
+            vec![id.clone()]
+        };
+        for candidate in candidates {
+            if !expanded.contains(&candidate) {
+                expanded.push(candidate);
+            }
+        }
+    }
+    expanded
+}
+
+fn preserved_local_core(
+    skill_dir: &Path,
+    core_file_path: &Path,
+    core_file_name: &str,
+    remote: &str,
+    force: bool,
+) -> Result<Option<Vec<u8>>, String> {
+    let Some(recorded) = read_skill_source(skill_dir).and_then(|record| record.core_hash) else {
+        return Ok(None);
+    };
+    let Ok(local) = fs::read(core_file_path) else {
+        return Ok(None);
+    };
+    if force || hash_bytes(&local) == recorded {
+        return Ok(None);
+    }
+    if hash_bytes(remote.as_bytes()) == recorded {
+        return Ok(Some(local));
+    }
+    Err(format!(
+        "local_modified: {} has local changes and the upstream also changed; sync with force to overwrite",
+        core_file_name
+    ))
+}
+
+struct UpstreamCore {
+    content: String,
+    github: Option<(GithubLocation, String)>,
+}
+
+fn fetch_upstream_core(
+    url: &str,
+    core_file_name: &str,
+    branch: Option<&str>,
+) -> Result<UpstreamCore, String> {
+    let Ok(mut location) = parse_github_location(url) else {
+        let candidates = parse_skill_urls(url, core_file_name)?;
+        let content = fetch_skill_content(candidates, core_file_name)?;
+        return Ok(UpstreamCore {
+            content,
+            github: None,
+        });
+    };
+
+    if let Some(branch) = branch {
+        location.branch = Some(branch.to_string());
+    }
+    let agent = http_agent();
+    let branches = github_branch_candidates(&agent, &location);
+    let path = github_file_path(&location, core_file_name);
+    let mut last_error = None;
+    for branch in branches.clone() {
+        match fetch_github_file_content(&agent, &location.owner, &location.repo, &path, &branch)
+            .and_then(|bytes| {
+                String::from_utf8(bytes).map_err(|err| format!("GitHub file is not UTF-8: {}", err))
+            }) {
+            Ok(content) => {
+                return Ok(UpstreamCore {
+                    content,
+                    github: Some((location, branch)),
+                });
+            }
+            Err(err) => {
+                last_error = Some(err);
+            }
+        }
+    }
+    Err(branches_tried_error(&path, &branches, last_error))
+}
+
+fn sync_skill_dir(
+    source: &SourceConfig,
+    skill_dir: &Path,
+    url: &str,
+    branch: Option<&str>,
+    force: bool,
+) -> Result<(SkillItem, DownloadStats), String> {
+    require_skill_directory(skill_dir, "synced")?;
+    let (core_file_path, core_file_name) = skill_core_file(skill_dir, source)?;
+
+    let requested_branch = branch
+        .map(|branch| branch.trim().to_string())
+        .filter(|branch| !branch.is_empty())
+        .or_else(|| {
+            read_skill_source(skill_dir)
+                .filter(|record| record.url == url.trim())
+                .and_then(|record| record.branch)
+        });
+    let (upstream, archive) = if is_zip_source(url) {
+        let remote = fetch_skill_archive(url, &[core_file_name.as_str()])?;
+        let upstream = UpstreamCore {
+            content: remote.content,
+            github: None,
+        };
+        (upstream, remote.archive)
+    } else {
+        let upstream = fetch_upstream_core(url, &core_file_name, requested_branch.as_deref())?;
+        (upstream, None)
+    };
+    let preserved = preserved_local_core(
+        skill_dir,
+        &core_file_path,
+        &core_file_name,
+        &upstream.content,
+        force,
+    )?;
+    let staging = std::env::temp_dir().join(format!(
+        "ananke-sync-{}-{}-{}",
+        skill_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        std::process::id(),
+        unix_timestamp()
+    ));
+    let downloaded = match (&upstream.github, &archive) {
+        (Some((location, branch)), _) => {
+            download_github_directory(&http_agent(), location, branch, &core_file_name, &staging)
+                .map(|stats| (requested_branch, Some(stats)))
+        }
+        (None, Some(archive)) => {
+            extract_skill_archive(archive, &staging).map(|stats| (None, Some(stats)))
+        }
+        (None, None) => Ok((None, None)),
+    };
+    let applied = downloaded.and_then(|(branch, stats)| match stats {
+        Some(stats) => {
+            fs::create_dir_all(&staging)
+                .map_err(|err| format!("Failed to create {}: {}", staging.display(), err))?;
+            apply_upstream_tree(&staging, skill_dir)?;
+            Ok((branch, stats))
+        }
+        None => Ok((
+            branch,
+            DownloadStats {
+                files: 1,
+                bytes: upstream.content.len() as u64,
+            },
+        )),
+    });
+    let _ = fs::remove_dir_all(&staging);
+    let (branch, stats) = applied?;
+
+    write_skill_source(
+        skill_dir,
+        &SkillSourceRecord {
+            url: url.trim().to_string(),
+            branch,
+            core_hash: Some(hash_bytes(upstream.content.as_bytes())),
         },
-    ]
+    )?;
+    let bytes = preserved.unwrap_or_else(|| upstream.content.into_bytes());
+    fs::write(&core_file_path, bytes).map_err(|err| write_error("File", &core_file_path, err))?;
+
+    let skill = load_skill(skill_dir, &core_file_path, &core_file_name, source)?;
+    Ok((skill, stats))
+}
+
+fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            tag: match change.tag() {
+                ChangeTag::Equal => "equal",
+                ChangeTag::Insert => "insert",
+                ChangeTag::Delete => "delete",
+            }
+            .to_string(),
+            content: change.value().trim_end_matches(['\r', '\n']).to_string(),
+        })
+        .collect()
+}
+
+fn list_github_files(
+    agent: &ureq::Agent,
+    location: &GithubLocation,
+    branch: &str,
+    repo_path: &str,
+    prefix: &str,
+    files: &mut Vec<String>,
+) -> Result<(), String> {
+    let entries = fetch_github_contents(agent, &location.owner, &location.repo, repo_path, branch)?;
+    for entry in entries {
+        let relative = if prefix.is_empty() {
+            entry.name.clone()
+        } else {
+            format!("{}/{}", prefix, entry.name)
+        };
+        match entry.item_type.as_str() {
+            "dir" => list_github_files(agent, location, branch, &entry.path, &relative, files)?,
+            "file" => files.push(relative),
+            _ => {}
+        }
+    }
+    Ok(())
 }
 
-fn mcp_source_configs(home: &Path) -> Vec<McpSourceConfig> {
-    let claude_primary = home.join(".claude.json");
-    let claude_alt = home.join(".claude").join(".mcp.json");
-    let claude_legacy = home.join(".claude").join("mcp.json");
-    let codex_path = home.join(".codex").join("config.toml");
-    let opencode_path = home.join(".config").join("opencode").join("opencode.json");
-    let roo_path = home.join(".roo").join("mcp.json");
-    let copilot_path = home.join(".copilot").join("mcp.json");
-    let cursor_path = home.join(".cursor").join("mcp.json");
-    let gemini_path = home.join(".gemini").join("settings.json");
-    let gemini_legacy = home.join(".gemini").join("mcp.json");
-    let trae_path = home.join(".trae").join("mcp.json");
-    let goose_path = home.join(".config").join("goose").join("mcp.json");
-    let antigravity_primary = home
-        .join(".gemini")
-        .join("antigravity")
-        .join("mcp_config.json");
-    let antigravity_legacy = home.join(".antigravity").join("mcp.json");
-    let antigravity_path = if antigravity_primary.exists() || !antigravity_legacy.exists() {
-        antigravity_primary.clone()
+#[tauri::command]
+fn sync_skill_from_url(payload: SyncSkillInput) -> Result<SkillItem, String> {
+    let source_id = payload.source_id.clone();
+    let skill_id = payload.skill_id.clone();
+    let url = payload.url.clone();
+    let result = sync_skill(payload);
+    record_history("sync_skill", &source_id, &skill_id, Some(&url), &result);
+    result
+}
+
+fn sync_skill(payload: SyncSkillInput) -> Result<SkillItem, String> {
+    ensure_online()?;
+    let _guard = override_github_token(payload.token.as_deref());
+
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let vanished = || format!("Skill {} no longer exists", payload.skill_id);
+    if !source.root.join(&payload.skill_id).exists() {
+        if !payload.recreate {
+            return Err(vanished());
+        }
+        return recreate_skill_dir(source, &payload.skill_id, &payload.url);
+    }
+    let skill_dir = resolve_skill_dir(source, &payload.skill_id, "sync").map_err(|err| {
+        if source.root.join(&payload.skill_id).exists() {
+            err
+        } else {
+            vanished()
+        }
+    })?;
+    sync_skill_dir(
+        source,
+        &skill_dir,
+        &payload.url,
+        payload.branch.as_deref(),
+        payload.force,
+    )
+    .map(|(skill, _)| skill)
+}
+
+fn recreate_skill_dir(
+    source: &SourceConfig,
+    skill_id: &str,
+    url: &str,
+) -> Result<SkillItem, String> {
+    if safe_relative_path(skill_id).is_none_or(|path| path.components().count() != 1) {
+        return Err("Invalid skill id".to_string());
+    }
+    fs::create_dir_all(&source.root)
+        .map_err(|err| format!("Failed to create {}: {}", source.root.display(), err))?;
+
+    let remote = fetch_remote_core(url, &source.core_files)?;
+    let skill_dir = source.root.join(skill_id);
+    if let Err(err) = write_remote_skill(remote, url, &skill_dir) {
+        let _ = fs::remove_dir_all(&skill_dir);
+        return Err(err);
+    }
+    let (core_path, core_file_name) = skill_core_file(&skill_dir, source)?;
+    load_skill(&skill_dir, &core_path, &core_file_name, source)
+}
+
+#[tauri::command]
+fn sync_skill_core_only(payload: SyncSkillCoreInput) -> Result<SkillItem, String> {
+    ensure_online()?;
+    let _guard = override_github_token(payload.token.as_deref());
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let skill_dir = resolve_skill_dir(source, &payload.skill_id, "sync")?;
+    let result = refresh_skill_core(source, &skill_dir, payload.force);
+    record_history(
+        "sync_skill_core",
+        &payload.source_id,
+        &payload.skill_id,
+        read_skill_source_url(&skill_dir).as_deref(),
+        &result,
+    );
+    result
+}
+
+fn refresh_skill_core(
+    source: &SourceConfig,
+    skill_dir: &Path,
+    force: bool,
+) -> Result<SkillItem, String> {
+    require_skill_directory(skill_dir, "synced")?;
+    let (core_file_path, core_file_name) = skill_core_file(skill_dir, source)?;
+    let record = read_skill_source(skill_dir)
+        .ok_or_else(|| "Skill has no recorded source URL".to_string())?;
+
+    let content = if is_zip_source(&record.url) {
+        fetch_skill_archive(&record.url, &[core_file_name.as_str()])?.content
     } else {
-        antigravity_legacy.clone()
+        fetch_upstream_core(&record.url, &core_file_name, record.branch.as_deref())?.content
     };
-    let antigravity_root = antigravity_path
+    let preserved =
+        preserved_local_core(skill_dir, &core_file_path, &core_file_name, &content, force)?;
+
+    write_skill_source(
+        skill_dir,
+        &SkillSourceRecord {
+            core_hash: Some(hash_bytes(content.as_bytes())),
+            ..record
+        },
+    )?;
+    let bytes = preserved.unwrap_or_else(|| content.into_bytes());
+    fs::write(&core_file_path, bytes).map_err(|err| write_error("File", &core_file_path, err))?;
+
+    load_skill(skill_dir, &core_file_path, &core_file_name, source)
+}
+
+#[tauri::command]
+fn reset_skill_to_upstream(payload: ResetSkillInput) -> Result<SkillItem, String> {
+    if !payload.confirm {
+        return Err("Reset discards local changes; set confirm to proceed".to_string());
+    }
+    ensure_online()?;
+    let _guard = override_github_token(payload.token.as_deref());
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let skill_dir = resolve_skill_dir(source, &payload.skill_id, "reset")?;
+    let is_real_dir = fs::symlink_metadata(&skill_dir)
+        .map(|metadata| metadata.file_type().is_dir())
+        .unwrap_or(false);
+    if !is_real_dir {
+        return Err("Only skill directories can be reset".to_string());
+    }
+    let record = read_skill_source(&skill_dir)
+        .ok_or_else(|| "Skill has no recorded source URL".to_string())?;
+    let (core_file_path, core_file_name) = skill_core_file(&skill_dir, source)?;
+
+    let result = reset_skill_dir(
+        source,
+        &skill_dir,
+        &core_file_path,
+        &core_file_name,
+        &record,
+    );
+    record_history(
+        "reset_skill",
+        &payload.source_id,
+        &payload.skill_id,
+        Some(&record.url),
+        &result,
+    );
+    result
+}
+
+fn reset_skill_dir(
+    source: &SourceConfig,
+    skill_dir: &Path,
+    core_file_path: &Path,
+    core_file_name: &str,
+    record: &SkillSourceRecord,
+) -> Result<SkillItem, String> {
+    let parent = skill_dir
         .parent()
-        .map(|parent| parent.to_path_buf())
-        .unwrap_or_else(|| home.join(".gemini").join("antigravity"));
-    let kiro_path = home.join(".kiro").join("mcp.json");
-    let qoder_path = home.join(".qoder").join("mcp.json");
-    let codebuddy_path = home.join(".codebuddy").join("mcp.json");
+        .ok_or_else(|| "Invalid skill directory".to_string())?;
+    let dir_name = skill_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| "Invalid skill directory".to_string())?;
+    let stamp = format!("{}-{}", std::process::id(), unix_timestamp());
+    let staging = parent.join(format!(".{}.reset-{}", dir_name, stamp));
+    let previous = parent.join(format!(".{}.previous-{}", dir_name, stamp));
+
+    let staged = fs::create_dir_all(&staging)
+        .map_err(|err| format!("Failed to create {}: {}", staging.display(), err))
+        .and_then(|_| {
+            let staged_core = staging.join(core_file_name);
+            fs::copy(core_file_path, &staged_core)
+                .map_err(|err| write_error("File", &staged_core, err))
+        })
+        .and_then(|_| write_skill_source(&staging, record))
+        .and_then(|_| {
+            sync_skill_dir(
+                source,
+                &staging,
+                &record.url,
+                record.branch.as_deref(),
+                true,
+            )
+        });
+    if let Err(err) = staged {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(err);
+    }
+
+    if let Err(err) = fs::rename(skill_dir, &previous) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(format!("Failed to move {}: {}", skill_dir.display(), err));
+    }
+    if let Err(err) = fs::rename(&staging, skill_dir) {
+        let _ = fs::rename(&previous, skill_dir);
+        let _ = fs::remove_dir_all(&staging);
+        return Err(format!(
+            "Failed to replace {}: {}",
+            skill_dir.display(),
+            err
+        ));
+    }
+    let _ = fs::remove_dir_all(&previous);
+
+    let (core_path, core_name) = skill_core_file(skill_dir, source)?;
+    load_skill(skill_dir, &core_path, &core_name, source)
+}
+
+#[tauri::command]
+fn sync_all_skills(payload: SyncAllSkillsInput) -> Result<Vec<SkillSyncReport>, String> {
+    ensure_online()?;
+    let _guard = override_github_token(payload.token.as_deref());
+
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let mut reports = Vec::new();
+    for skill in read_skills(source) {
+        let Some(url) = skill.source_url.clone() else {
+            continue;
+        };
+        let before = compute_skill_hash(Path::new(&skill.path)).ok();
+        let result = resolve_skill_dir(source, &skill.id, "sync")
+            .and_then(|skill_dir| sync_skill_dir(source, &skill_dir, &url, None, false))
+            .map(|(skill, _)| skill);
+        record_history(
+            "sync_skill",
+            &payload.source_id,
+            &skill.id,
+            Some(&url),
+            &result,
+        );
+        let report = match result {
+            Ok(updated) => {
+                let after = compute_skill_hash(Path::new(&updated.path)).ok();
+                let status = if before == after {
+                    "unchanged"
+                } else {
+                    "updated"
+                };
+                SkillSyncReport {
+                    skill_id: skill.id,
+                    status: status.to_string(),
+                    error: None,
+                }
+            }
+            Err(err) => SkillSyncReport {
+                skill_id: skill.id,
+                status: "failed".to_string(),
+                error: Some(err),
+            },
+        };
+        reports.push(report);
+    }
+
+    Ok(reports)
+}
+
+#[tauri::command]
+fn diff_skill_against_upstream(payload: DiffSkillInput) -> Result<SkillDiff, String> {
+    ensure_online()?;
+    let _guard = override_github_token(payload.token.as_deref());
+
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let skill_dir = resolve_skill_dir(source, &payload.skill_id, "read")?;
+    let record = read_skill_source(&skill_dir)
+        .ok_or_else(|| "Skill has no recorded source URL".to_string())?;
+    diff_skill_dir_against(source, &skill_dir, &record.url, record.branch.as_deref())
+}
+
+#[tauri::command]
+fn diff_skill_against_url(payload: DiffSkillUrlInput) -> Result<SkillDiff, String> {
+    ensure_online()?;
+    let _guard = override_github_token(payload.token.as_deref());
+
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let skill_dir = resolve_skill_dir(source, &payload.skill_id, "read")?;
+    diff_skill_dir_against(source, &skill_dir, &payload.url, None)
+}
+
+fn diff_skill_dir_against(
+    source: &SourceConfig,
+    skill_dir: &Path,
+    url: &str,
+    branch: Option<&str>,
+) -> Result<SkillDiff, String> {
+    require_skill_directory(skill_dir, "diffed against upstream")?;
+    let (core_file_path, core_file_name) = skill_core_file(skill_dir, source)?;
+    let local = fs::read_to_string(&core_file_path)
+        .map_err(|err| format!("Failed to read {}: {}", core_file_path.display(), err))?;
+
+    let upstream = fetch_upstream_core(url, &core_file_name, branch)?;
+    let lines = diff_lines(&local, &upstream.content);
+
+    let mut added_files = Vec::new();
+    let mut removed_files = Vec::new();
+    if let Some((location, branch)) = &upstream.github {
+        let mut remote_files = Vec::new();
+        list_github_files(
+            &http_agent(),
+            location,
+            branch,
+            &github_skill_dir_path(location, &core_file_name),
+            "",
+            &mut remote_files,
+        )?;
+        let mut local_files = Vec::new();
+        collect_skill_files(skill_dir, "", &mut local_files)?;
+        let local_set: HashSet<String> = local_files.into_iter().map(|(path, _)| path).collect();
+        let remote_set: HashSet<String> = remote_files.into_iter().collect();
+        added_files = remote_set.difference(&local_set).cloned().collect();
+        removed_files = local_set.difference(&remote_set).cloned().collect();
+        added_files.sort();
+        removed_files.sort();
+    }
+
+    Ok(SkillDiff {
+        core_file: core_file_name,
+        changed: local != upstream.content || !added_files.is_empty() || !removed_files.is_empty(),
+        lines,
+        added_files,
+        removed_files,
+    })
+}
+
+#[tauri::command]
+fn set_skill_source_url(payload: SetSkillSourceUrlInput) -> Result<SkillItem, String> {
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let skill_dir = resolve_skill_dir(source, &payload.skill_id, "update")?;
+    require_skill_directory(&skill_dir, "linked to a source URL")?;
+    let (core_file_path, core_file_name) = skill_core_file(&skill_dir, source)?;
+
+    parse_skill_urls(&payload.url, &core_file_name)?;
+    write_skill_source_url(&skill_dir, &payload.url)?;
+
+    load_skill(&skill_dir, &core_file_path, &core_file_name, source)
+}
+
+fn validate_metadata_entry<'a>(
+    key: &'a str,
+    value: Option<&'a str>,
+) -> Result<(&'a str, Option<&'a str>), String> {
+    let key = key.trim();
+    if key.is_empty() || key.contains(':') || key.starts_with('#') {
+        return Err("Invalid metadata key".to_string());
+    }
+    let value = value.map(str::trim);
+    if value.is_some_and(|value| value.contains(['\n', '\r'])) {
+        return Err("Metadata value must be a single line".to_string());
+    }
+    Ok((key, value))
+}
+
+fn write_skill_metadata(
+    source: &SourceConfig,
+    skill_id: &str,
+    key: &str,
+    value: Option<&str>,
+) -> Result<(SkillItem, bool), String> {
+    let skill_dir = resolve_skill_dir(source, skill_id, "update")?;
+    let (core_file_path, core_file_name) = skill_core_file(&skill_dir, source)?;
+    if !core_file_name.to_ascii_lowercase().ends_with(".md") {
+        return Err(format!("{} has no frontmatter", core_file_name));
+    }
+
+    let raw = fs::read_to_string(&core_file_path)
+        .map_err(|err| format!("Failed to read {}: {}", core_file_path.display(), err))?;
+    let updated = set_frontmatter_value(&raw, key, value);
+    let changed = updated != raw;
+    if changed {
+        fs::write(&core_file_path, updated)
+            .map_err(|err| write_error("File", &core_file_path, err))?;
+    }
+
+    let skill = load_skill(&skill_dir, &core_file_path, &core_file_name, source)?;
+    Ok((skill, changed))
+}
+
+#[tauri::command]
+fn set_skill_metadata(payload: SetSkillMetadataInput) -> Result<SkillItem, String> {
+    let (key, value) = validate_metadata_entry(&payload.key, payload.value.as_deref())?;
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    write_skill_metadata(source, &payload.skill_id, key, value).map(|(skill, _)| skill)
+}
+
+#[tauri::command]
+fn bulk_set_metadata(payload: BulkSetMetadataInput) -> Result<Vec<SkillSyncReport>, String> {
+    let (key, value) = validate_metadata_entry(&payload.key, payload.value.as_deref())?;
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let skill_ids = match payload.skill_ids {
+        Some(ids) => ids,
+        None => read_skills(source)
+            .into_iter()
+            .map(|skill| skill.id)
+            .collect(),
+    };
+    Ok(skill_ids
+        .into_iter()
+        .map(
+            |skill_id| match write_skill_metadata(source, &skill_id, key, value) {
+                Ok((_, changed)) => SkillSyncReport {
+                    skill_id,
+                    status: if changed { "updated" } else { "unchanged" }.to_string(),
+                    error: None,
+                },
+                Err(err) => SkillSyncReport {
+                    skill_id,
+                    status: "failed".to_string(),
+                    error: Some(err),
+                },
+            },
+        )
+        .collect())
+}
+
+#[tauri::command]
+fn normalize_skill_slug(payload: SkillTreeInput) -> Result<SkillItem, String> {
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let skill_dir = resolve_skill_dir(source, &payload.skill_id, "rename")?;
+    require_skill_directory(&skill_dir, "renamed")?;
+    let (core_file_path, core_file_name) = skill_core_file(&skill_dir, source)?;
+    let skill = load_skill(&skill_dir, &core_file_path, &core_file_name, source)?;
+    let Some(name) = skill
+        .metadata
+        .get("name")
+        .filter(|value| !value.trim().is_empty())
+    else {
+        return Ok(skill);
+    };
+    let slug = slugify(name);
+    if slug == payload.skill_id {
+        return Ok(skill);
+    }
+
+    let target = rename_skill_dir(source, &skill_dir, &slug)?;
+    load_skill(
+        &target,
+        &target.join(&core_file_name),
+        &core_file_name,
+        source,
+    )
+}
+
+fn rename_skill_dir(
+    source: &SourceConfig,
+    skill_dir: &Path,
+    slug: &str,
+) -> Result<PathBuf, String> {
+    let target = unique_skill_dir(&source.root, slug);
+    fs::rename(skill_dir, &target)
+        .map_err(|err| format!("Failed to rename {}: {}", skill_dir.display(), err))?;
+    Ok(target)
+}
+
+#[tauri::command]
+fn reslug_all_skills(payload: SkillSourceInput) -> Result<ReslugResult, String> {
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let mut result = ReslugResult {
+        renamed: HashMap::new(),
+        failed: HashMap::new(),
+    };
+    for skill in read_skills(source) {
+        let slug = slugify(&skill.name);
+        if slug == skill.id {
+            continue;
+        }
+        let renamed = resolve_skill_dir(source, &skill.id, "rename").and_then(|skill_dir| {
+            if skill_dir.is_file() {
+                return Err("Flat skill files are not renamed".to_string());
+            }
+            rename_skill_dir(source, &skill_dir, &slug)
+        });
+        match renamed {
+            Ok(target) => {
+                let new_id = target
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or(slug);
+                result.renamed.insert(skill.id, new_id);
+            }
+            Err(err) => {
+                result.failed.insert(skill.id, err);
+            }
+        }
+    }
+    Ok(result)
+}
 
-    vec![
-        McpSourceConfig {
-            id: "claude",
-            label: "Claude Code",
-            format: "json",
-            kind: McpKind::ClaudeJson,
-            install_root: home.join(".claude"),
-            primary_path: claude_primary.clone(),
-            read_paths: vec![claude_primary, claude_alt, claude_legacy],
-        },
-        McpSourceConfig {
-            id: "roo",
-            label: "Roo Code (Cline)",
-            format: "json",
-            kind: McpKind::ClaudeJson,
-            install_root: home.join(".roo"),
-            primary_path: roo_path.clone(),
-            read_paths: vec![roo_path],
-        },
-        McpSourceConfig {
-            id: "copilot",
-            label: "GitHub Copilot",
-            format: "json",
-            kind: McpKind::ClaudeJson,
-            install_root: home.join(".copilot"),
-            primary_path: copilot_path.clone(),
-            read_paths: vec![copilot_path],
-        },
-        McpSourceConfig {
-            id: "cursor",
-            label: "Cursor",
-            format: "json",
-            kind: McpKind::ClaudeJson,
-            install_root: home.join(".cursor"),
-            primary_path: cursor_path.clone(),
-            read_paths: vec![cursor_path],
-        },
-        McpSourceConfig {
-            id: "gemini",
-            label: "Gemini CLI",
-            format: "json",
-            kind: McpKind::ClaudeJson,
-            install_root: home.join(".gemini"),
-            primary_path: gemini_path.clone(),
-            read_paths: vec![gemini_path, gemini_legacy],
-        },
-        McpSourceConfig {
-            id: "codex",
-            label: "Codex",
-            format: "toml",
-            kind: McpKind::CodexToml,
-            install_root: home.join(".codex"),
-            primary_path: codex_path.clone(),
-            read_paths: vec![codex_path],
-        },
-        McpSourceConfig {
-            id: "opencode",
-            label: "OpenCode",
-            format: "json",
-            kind: McpKind::OpenCodeJson,
-            install_root: home.join(".config").join("opencode"),
-            primary_path: opencode_path.clone(),
-            read_paths: vec![opencode_path],
-        },
-        McpSourceConfig {
-            id: "trae",
-            label: "Trae",
-            format: "json",
-            kind: McpKind::ClaudeJson,
-            install_root: home.join(".trae"),
-            primary_path: trae_path.clone(),
-            read_paths: vec![trae_path],
-        },
-        McpSourceConfig {
-            id: "goose",
-            label: "Goose",
-            format: "json",
-            kind: McpKind::ClaudeJson,
-            install_root: home.join(".config").join("goose"),
-            primary_path: goose_path.clone(),
-            read_paths: vec![goose_path],
-        },
-        McpSourceConfig {
-            id: "antigravity",
-            label: "Antigravity",
-            format: "json",
-            kind: McpKind::AntigravityJson,
-            install_root: antigravity_root,
-            primary_path: antigravity_path.clone(),
-            read_paths: vec![antigravity_primary, antigravity_legacy],
-        },
-        McpSourceConfig {
-            id: "kiro",
-            label: "Kiro",
-            format: "json",
-            kind: McpKind::ClaudeJson,
-            install_root: home.join(".kiro"),
-            primary_path: kiro_path.clone(),
-            read_paths: vec![kiro_path],
-        },
-        McpSourceConfig {
-            id: "qoder",
-            label: "Qoder",
-            format: "json",
-            kind: McpKind::ClaudeJson,
-            install_root: home.join(".qoder"),
-            primary_path: qoder_path.clone(),
-            read_paths: vec![qoder_path],
-        },
-        McpSourceConfig {
-            id: "codebuddy",
-            label: "CodeBuddy",
-            format: "json",
-            kind: McpKind::ClaudeJson,
-            install_root: home.join(".codebuddy"),
-            primary_path: codebuddy_path.clone(),
-            read_paths: vec![codebuddy_path],
-        },
-    ]
+fn icon_mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "svg" => Some("image/svg+xml"),
+        "ico" => Some("image/x-icon"),
+        _ => None,
+    }
 }
 
-fn resolve_read_path(config: &McpSourceConfig) -> PathBuf {
-    for path in &config.read_paths {
-        if path.exists() {
-            return path.clone();
+#[tauri::command]
+fn get_skill_icon(payload: SkillTreeInput) -> Result<Option<SkillIcon>, String> {
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let skill_dir = resolve_skill_dir(source, &payload.skill_id, "read")?;
+    if !skill_dir.is_dir() {
+        return Ok(None);
+    }
+    let mut candidates = Vec::new();
+    if let Ok(manifest) = load_json_value(&skill_dir.join("manifest.json")) {
+        if let Some(icon) = manifest.get("icon").and_then(|item| item.as_str()) {
+            candidates.push(icon.trim().to_string());
+        }
+    }
+    candidates.extend(
+        ["icon.png", "icon.svg", "icon.jpg", "icon.webp"]
+            .iter()
+            .map(|name| name.to_string()),
+    );
+
+    let skill_canon =
+        fs::canonicalize(&skill_dir).map_err(|err| format!("Failed to resolve skill: {}", err))?;
+    for candidate in candidates {
+        let Ok(path) = fs::canonicalize(skill_dir.join(&candidate)) else {
+            continue;
+        };
+        if !path.starts_with(&skill_canon) {
+            return Err("Refusing to read icon outside skill directory".to_string());
         }
+        let Some(mime_type) = icon_mime_type(&path) else {
+            continue;
+        };
+        let size = fs::metadata(&path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?
+            .len();
+        if size > MAX_SKILL_ICON_BYTES {
+            return Err(format!(
+                "Icon {} exceeds {} bytes",
+                candidate, MAX_SKILL_ICON_BYTES
+            ));
+        }
+        return read_skill_icon(&path, mime_type).map(Some);
     }
-    config.primary_path.clone()
+    Ok(None)
 }
 
-fn parse_frontmatter(raw: &str) -> (HashMap<String, String>, String) {
-    let mut metadata = HashMap::new();
-    let mut lines = raw.lines();
+fn read_skill_icon(path: &Path, mime_type: &str) -> Result<SkillIcon, String> {
+    static SKILL_ICONS: OnceLock<Mutex<SkillIconCache>> = OnceLock::new();
+    let cache = SKILL_ICONS.get_or_init(|| Mutex::new(HashMap::new()));
+    let metadata =
+        fs::metadata(path).map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+    let modified = metadata.modified().ok();
+    let size = metadata.len();
+    if let Some(icon) = cache.lock().ok().and_then(|cache| {
+        cache
+            .get(path)
+            .filter(|(cached_modified, cached_size, _)| {
+                *cached_modified == modified && *cached_size == size
+            })
+            .map(|(_, _, icon)| icon.clone())
+    }) {
+        return Ok(icon);
+    }
 
-    if !matches!(lines.next(), Some(line) if line.trim() == "---") {
-        return (metadata, raw.to_string());
+    let bytes =
+        fs::read(path).map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+    let icon = SkillIcon {
+        mime_type: mime_type.to_string(),
+        data: base64::engine::general_purpose::STANDARD.encode(bytes),
+    };
+    if let Ok(mut cache) = cache.lock() {
+        cache.insert(path.to_path_buf(), (modified, size, icon.clone()));
     }
+    Ok(icon)
+}
 
-    let mut frontmatter = Vec::new();
-    let mut found_close = false;
+#[tauri::command]
+fn canonical_skill_url(payload: SkillTreeInput) -> Result<String, String> {
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
 
-    for line in lines.by_ref() {
-        if line.trim() == "---" {
-            found_close = true;
-            break;
+    let skill_dir = resolve_skill_dir(source, &payload.skill_id, "read")?;
+    let record = read_skill_source(&skill_dir)
+        .ok_or_else(|| "Skill has no recorded source URL".to_string())?;
+    github_tree_url(&record.url, record.branch.as_deref())
+}
+
+#[tauri::command]
+fn diff_skill_trees(payload: DiffSkillTreesInput) -> Result<SkillTreeDiff, String> {
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let find_source = |source_id: &str| {
+        sources
+            .iter()
+            .find(|source| source.id == source_id)
+            .ok_or_else(|| "Unknown skill source".to_string())
+    };
+
+    let a_dir = resolve_skill_dir(
+        find_source(&payload.a_source_id)?,
+        &payload.a_skill_id,
+        "read",
+    )?;
+    let b_dir = resolve_skill_dir(
+        find_source(&payload.b_source_id)?,
+        &payload.b_skill_id,
+        "read",
+    )?;
+    let a_files = skill_file_hashes(&a_dir)?;
+    let b_files = skill_file_hashes(&b_dir)?;
+
+    let mut added: Vec<String> = b_files
+        .keys()
+        .filter(|path| !a_files.contains_key(*path))
+        .cloned()
+        .collect();
+    let mut removed: Vec<String> = a_files
+        .keys()
+        .filter(|path| !b_files.contains_key(*path))
+        .cloned()
+        .collect();
+    let mut changed: Vec<String> = a_files
+        .iter()
+        .filter(|(path, hash)| b_files.get(*path).is_some_and(|other| other != *hash))
+        .map(|(path, _)| path.clone())
+        .collect();
+    added.sort();
+    removed.sort();
+    changed.sort();
+
+    Ok(SkillTreeDiff {
+        identical: added.is_empty() && removed.is_empty() && changed.is_empty(),
+        added,
+        removed,
+        changed,
+    })
+}
+
+#[tauri::command]
+fn skill_hash(payload: SkillTreeInput) -> Result<String, String> {
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let skill_dir = resolve_skill_dir(source, &payload.skill_id, "read")?;
+    compute_skill_hash(&skill_dir)
+}
+
+#[tauri::command]
+fn find_skill_everywhere(payload: FindSkillInput) -> Result<Vec<SkillLocation>, String> {
+    let query = payload.query.trim();
+    if query.is_empty() {
+        return Err("Query is required".to_string());
+    }
+    let is_hash = query.len() == 64 && query.chars().all(|ch| ch.is_ascii_hexdigit());
+    let home = resolve_home()?;
+
+    let mut locations = Vec::new();
+    for source in source_configs(&home) {
+        for skill in read_skills(&source) {
+            let matches = skill.id.eq_ignore_ascii_case(query)
+                || skill.name.trim().eq_ignore_ascii_case(query)
+                || (is_hash
+                    && Path::new(&skill.path).is_dir()
+                    && compute_skill_hash(Path::new(&skill.path))
+                        .is_ok_and(|hash| hash.eq_ignore_ascii_case(query)));
+            if matches {
+                locations.push(SkillLocation {
+                    source_id: source.id.to_string(),
+                    skill_id: skill.id,
+                });
+            }
         }
-        frontmatter.push(line);
     }
+    Ok(locations)
+}
 
-    if !found_close {
-        return (HashMap::new(), raw.to_string());
+#[tauri::command]
+fn relink_skills(payload: SkillSourceInput) -> Result<Vec<RelinkCandidate>, String> {
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let unlinked: Vec<(String, String)> = read_skills(source)
+        .into_iter()
+        .filter(|skill| Path::new(&skill.path).is_dir() && skill.source_url.is_none())
+        .filter_map(|skill| {
+            let hash = compute_skill_hash(Path::new(&skill.path)).ok()?;
+            Some((skill.id, hash))
+        })
+        .collect();
+    if unlinked.is_empty() {
+        return Ok(Vec::new());
     }
 
-    for line in frontmatter {
-        if let Some((key, value)) = line.split_once(':') {
-            let key = key.trim();
-            if key.is_empty() {
+    let mut linked: HashMap<String, Vec<(String, String, String)>> = HashMap::new();
+    for other in sources.iter().filter(|other| other.id != source.id) {
+        for skill in read_skills(other) {
+            let skill_dir = Path::new(&skill.path);
+            if !skill_dir.is_dir() {
                 continue;
             }
-            metadata.insert(key.to_string(), value.trim().to_string());
+            let Some(record) = read_skill_source(skill_dir) else {
+                continue;
+            };
+            let Ok(hash) = compute_skill_hash(skill_dir) else {
+                continue;
+            };
+            linked
+                .entry(hash)
+                .or_default()
+                .push((other.id.to_string(), skill.id, record.url));
         }
     }
 
-    let body = lines.collect::<Vec<_>>().join("\n");
-    (metadata, body.trim_start().to_string())
+    let mut candidates = Vec::new();
+    for (skill_id, hash) in unlinked {
+        for (from_source_id, from_skill_id, url) in linked.get(&hash).into_iter().flatten() {
+            candidates.push(RelinkCandidate {
+                skill_id: skill_id.clone(),
+                from_source_id: from_source_id.clone(),
+                from_skill_id: from_skill_id.clone(),
+                url: url.clone(),
+            });
+        }
+    }
+    Ok(candidates)
 }
 
-fn extract_description(body: &str) -> String {
-    for line in body.lines() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        if trimmed.starts_with('#') {
+#[tauri::command]
+fn prune_orphaned_skill_source_files(
+    payload: PruneSkillSourcesInput,
+) -> Result<Vec<SkillSyncReport>, String> {
+    ensure_online()?;
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let agent = http_agent();
+    let mut reports = Vec::new();
+    for skill in read_skills(source) {
+        let Some(url) = skill.source_url.clone() else {
             continue;
-        }
-        return trimmed.to_string();
+        };
+        let (status, error) = match agent.head(url.trim()).call() {
+            Ok(_) => ("reachable", None),
+            Err(ureq::Error::Status(code @ (404 | 410), _)) => (
+                "unreachable",
+                Some(format!("Upstream returned HTTP {}", code)),
+            ),
+            Err(ureq::Error::Status(code, _)) => {
+                ("unknown", Some(format!("Upstream returned HTTP {}", code)))
+            }
+            Err(err) => ("unreachable", Some(err.to_string())),
+        };
+        let status = if status == "unreachable" && payload.clear {
+            let record =
+                resolve_skill_dir(source, &skill.id, "update")?.join(SKILL_SOURCE_FILENAME);
+            fs::remove_file(&record)
+                .map_err(|err| format!("Failed to remove {}: {}", record.display(), err))?;
+            "cleared"
+        } else {
+            status
+        };
+        reports.push(SkillSyncReport {
+            skill_id: skill.id,
+            status: status.to_string(),
+            error,
+        });
     }
-    String::new()
+
+    Ok(reports)
 }
 
-fn load_skill(
-    skill_dir: &Path,
-    core_file_path: &Path,
-    core_file_name: &str,
-    source: &SourceConfig,
-) -> Result<SkillItem, String> {
-    let raw = fs::read_to_string(core_file_path)
+#[tauri::command]
+fn get_skill_description(payload: SkillDescriptionInput) -> Result<SkillDescription, String> {
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    let skill_dir = resolve_skill_dir(source, &payload.skill_id, "read")?;
+    let (core_file_path, core_file_name) = skill_core_file(&skill_dir, source)?;
+    ensure_core_link_within_root(&core_file_path, source)?;
+    let raw = fs::read_to_string(&core_file_path)
         .map_err(|err| format!("Failed to read {}: {}", core_file_path.display(), err))?;
-    let is_markdown = core_file_name.ends_with(".md");
-    let (metadata, body) = if is_markdown {
+    let (metadata, body) = if core_file_name.to_ascii_lowercase().ends_with(".md") {
         parse_frontmatter(&raw)
     } else {
-        (HashMap::new(), raw.clone())
+        (HashMap::new(), raw)
     };
-    let source_url = read_skill_source_url(skill_dir);
-    let dir_name = skill_dir
-        .file_name()
-        .and_then(|value| value.to_str())
-        .unwrap_or("skill");
-
-    let name = metadata
-        .get("name")
-        .cloned()
-        .filter(|value| !value.trim().is_empty())
-        .unwrap_or_else(|| dir_name.to_string());
-    let description = metadata
-        .get("description")
-        .cloned()
-        .filter(|value| !value.trim().is_empty())
-        .unwrap_or_else(|| {
-            if is_markdown {
-                extract_description(&body)
-            } else {
-                String::new()
-            }
-        });
-
-    let skill_md_path = skill_dir.join("SKILL.md");
-    let last_modified = fs::metadata(skill_md_path)
-        .or_else(|_| fs::metadata(core_file_path))
-        .and_then(|data| data.modified())
-        .ok()
-        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
-        .map(|duration| duration.as_secs());
 
-    Ok(SkillItem {
-        id: dir_name.to_string(),
-        name,
+    let (description, description_source) = skill_description(&metadata, &body);
+    Ok(SkillDescription {
         description,
-        path: skill_dir.display().to_string(),
-        core_file: core_file_name.to_string(),
-        core_file_path: core_file_path.display().to_string(),
-        source_url,
-        source_id: source.id.to_string(),
-        metadata,
-        body,
-        last_modified,
+        source: description_source.to_string(),
     })
 }
 
-fn read_skills(source: &SourceConfig) -> Vec<SkillItem> {
-    let mut skills = Vec::new();
+#[tauri::command]
+fn get_skill_commit(payload: SkillCommitInput) -> Result<SkillCommit, String> {
+    ensure_online()?;
+    let _guard = override_github_token(payload.token.as_deref());
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
 
-    let entries = match fs::read_dir(&source.root) {
-        Ok(entries) => entries,
-        Err(_) => return skills,
-    };
+    let skill_dir = resolve_skill_dir(source, &payload.skill_id, "read")?;
+    let (_, core_file_name) = skill_core_file(&skill_dir, source)?;
+    let record = read_skill_source(&skill_dir)
+        .ok_or_else(|| "Skill has no recorded source URL".to_string())?;
+    let mut location = parse_github_location(&record.url)
+        .map_err(|_| "Skill source is not a GitHub URL".to_string())?;
+    if let Some(branch) = record.branch {
+        location.branch = Some(branch);
+    }
 
-    for entry in entries.flatten() {
-        if let Ok(file_type) = entry.file_type() {
-            if !file_type.is_dir() {
-                continue;
-            }
-        }
+    let agent = http_agent();
+    let branch = github_branch_candidates(&agent, &location)
+        .into_iter()
+        .next()
+        .ok_or_else(|| "Unable to determine branch".to_string())?;
+    let mut url = Url::parse(&format!(
+        "{}/repos/{}/{}/commits",
+        github_api_base(),
+        location.owner,
+        location.repo
+    ))
+    .map_err(|err| format!("Invalid GitHub URL: {}", err))?;
+    url.query_pairs_mut()
+        .append_pair("path", &github_file_path(&location, &core_file_name))
+        .append_pair("sha", &branch)
+        .append_pair("per_page", "1");
+    let response = github_request(&agent, url.as_str())?
+        .call()
+        .map_err(|err| format!("Failed to read GitHub commits: {}", err))?;
+    let value =
+        read_json_response(response).map_err(|err| format!("Invalid GitHub response: {}", err))?;
+    let entry = value
+        .as_array()
+        .and_then(|items| items.first())
+        .ok_or_else(|| "No upstream commits touch the core file".to_string())?;
+    let commit = entry.get("commit");
+    let text = |value: Option<&JsonValue>| value.and_then(|item| item.as_str()).map(str::to_string);
+
+    Ok(SkillCommit {
+        sha: text(entry.get("sha")).ok_or_else(|| "Missing sha in GitHub response".to_string())?,
+        date: text(commit.and_then(|item| item.pointer("/author/date"))),
+        message: text(commit.and_then(|item| item.get("message"))).unwrap_or_default(),
+        author: text(entry.pointer("/author/login"))
+            .or_else(|| text(commit.and_then(|item| item.pointer("/author/name")))),
+    })
+}
 
-        let path = entry.path();
-        let core_file = find_core_file(&path, &source.core_files);
-        if let Some((core_file_path, core_file_name)) = core_file {
-            if let Ok(skill) = load_skill(&path, &core_file_path, &core_file_name, source) {
-                skills.push(skill);
-            }
-        }
-    }
+#[tauri::command]
+fn github_rate_limit(payload: GithubTokenInput) -> Result<GithubRateLimit, String> {
+    let _guard = override_github_token(payload.token.as_deref());
+    let url = format!("{}/rate_limit", github_api_base());
+    let response = github_request(&http_agent(), &url)?
+        .call()
+        .map_err(|err| format!("Failed to read GitHub rate limit: {}", err))?;
+    let value =
+        read_json_response(response).map_err(|err| format!("Invalid GitHub response: {}", err))?;
+    let core = value
+        .get("resources")
+        .and_then(|resources| resources.get("core"))
+        .or_else(|| value.get("rate"))
+        .ok_or_else(|| "Unexpected GitHub response".to_string())?;
+    let field = |name: &str| core.get(name).and_then(|item| item.as_u64()).unwrap_or(0);
+
+    Ok(GithubRateLimit {
+        limit: field("limit"),
+        remaining: field("remaining"),
+        reset: field("reset"),
+    })
+}
 
-    skills.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-    skills
+#[tauri::command]
+fn list_github_branches(payload: GithubBranchesInput) -> Result<Vec<GithubBranch>, String> {
+    ensure_online()?;
+    let _guard = override_github_token(payload.token.as_deref());
+    let location = parse_github_location(&payload.url)?;
+    let agent = http_agent();
+    let default_branch = fetch_github_default_branch(&agent, &location.owner, &location.repo).ok();
+    let names = fetch_github_branches(&agent, &location.owner, &location.repo)?;
+
+    let mut branches: Vec<GithubBranch> = names
+        .into_iter()
+        .map(|name| GithubBranch {
+            is_default: default_branch.as_deref() == Some(name.as_str()),
+            name,
+        })
+        .collect();
+    branches.sort_by(|a, b| {
+        b.is_default
+            .cmp(&a.is_default)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(branches)
 }
 
-fn copy_dir_recursive(src: &Path, dest: &Path) -> Result<(), String> {
-    fs::create_dir_all(dest)
-        .map_err(|err| format!("Failed to create {}: {}", dest.display(), err))?;
-    let entries =
-        fs::read_dir(src).map_err(|err| format!("Failed to read {}: {}", src.display(), err))?;
+fn remove_skill(source: &SourceConfig, skill_id: &str) -> Result<(), String> {
+    let skill_dir = source.root.join(skill_id);
+    if !skill_dir.exists() {
+        return Err("Skill not found".to_string());
+    }
 
-    for entry in entries {
-        let entry = entry.map_err(|err| format!("Failed to read entry: {}", err))?;
-        let file_type = entry
-            .file_type()
-            .map_err(|err| format!("Failed to read file type: {}", err))?;
-        let source_path = entry.path();
-        let target_path = dest.join(entry.file_name());
+    let root_canon =
+        fs::canonicalize(&source.root).map_err(|err| format!("Failed to resolve root: {}", err))?;
+    let skill_canon =
+        fs::canonicalize(&skill_dir).map_err(|err| format!("Failed to resolve skill: {}", err))?;
 
-        if file_type.is_dir() {
-            copy_dir_recursive(&source_path, &target_path)?;
-        } else {
-            fs::copy(&source_path, &target_path).map_err(|err| {
-                format!(
-                    "Failed to copy {} to {}: {}",
-                    source_path.display(),
-                    target_path.display(),
-                    err
-                )
-            })?;
-        }
+    if !skill_canon.starts_with(&root_canon) || skill_canon == root_canon {
+        return Err("Refusing to delete outside agent root".to_string());
     }
 
+    if skill_dir.is_file() {
+        fs::remove_file(&skill_dir).map_err(|err| format!("Failed to delete skill: {}", err))?;
+    } else {
+        fs::remove_dir_all(&skill_dir).map_err(|err| format!("Failed to delete skill: {}", err))?;
+    }
     Ok(())
 }
 
-fn find_core_file(skill_dir: &Path, core_files: &[&str]) -> Option<(PathBuf, String)> {
-    for file in core_files {
-        let path = skill_dir.join(file);
-        if path.is_file() {
-            return Some((path, file.to_string()));
-        }
-    }
-    None
-}
+#[tauri::command]
+fn delete_skill(payload: DeleteSkillInput) -> Result<(), String> {
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
 
-fn build_skill_tree(path: &Path) -> Result<SkillTreeNode, String> {
-    let metadata = fs::symlink_metadata(path)
-        .map_err(|err| format!("Failed to read metadata {}: {}", path.display(), err))?;
-    let file_type = metadata.file_type();
-    let name = path
-        .file_name()
-        .and_then(|value| value.to_str())
-        .map(|value| value.to_string())
-        .unwrap_or_else(|| path.to_string_lossy().to_string());
+    let result = remove_skill(source, &payload.skill_id);
+    record_history(
+        "delete_skill",
+        &payload.source_id,
+        &payload.skill_id,
+        None,
+        &result,
+    );
+    result
+}
 
-    let kind = if file_type.is_dir() {
-        "dir"
-    } else if file_type.is_symlink() {
-        "link"
-    } else {
-        "file"
-    };
+#[tauri::command]
+fn delete_skills(payload: DeleteSkillsInput) -> Result<Vec<SkillSyncReport>, String> {
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
 
-    let mut children = Vec::new();
-    if file_type.is_dir() {
-        let entries = fs::read_dir(path)
-            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
-        let mut items: Vec<_> = entries.filter_map(|entry| entry.ok()).collect();
-        items.sort_by(|a, b| {
-            let a_dir = a.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
-            let b_dir = b.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
-            if a_dir != b_dir {
-                return b_dir.cmp(&a_dir);
+    let reports = payload
+        .skill_ids
+        .into_iter()
+        .map(|skill_id| {
+            let result = remove_skill(source, &skill_id);
+            record_history("delete_skill", &payload.source_id, &skill_id, None, &result);
+            match result {
+                Ok(()) => SkillSyncReport {
+                    skill_id,
+                    status: "deleted".to_string(),
+                    error: None,
+                },
+                Err(err) => SkillSyncReport {
+                    skill_id,
+                    status: "failed".to_string(),
+                    error: Some(err),
+                },
             }
-            a.file_name()
-                .to_string_lossy()
-                .to_lowercase()
-                .cmp(&b.file_name().to_string_lossy().to_lowercase())
-        });
+        })
+        .collect();
+    Ok(reports)
+}
 
-        for entry in items {
-            let path = entry.path();
-            let child = build_skill_tree(&path)?;
-            children.push(child);
+#[tauri::command]
+fn sync_skills_from_agent(payload: SyncSkillsAgentsInput) -> Result<SyncResult, String> {
+    if payload.source_id == payload.target_id {
+        return Err("Source and target must be different".to_string());
+    }
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+    let target = sources
+        .iter()
+        .find(|source| source.id == payload.target_id)
+        .ok_or_else(|| "Unknown target source".to_string())?;
+
+    if !source.root.is_dir() {
+        return Err("Source skills directory missing".to_string());
+    }
+    fs::create_dir_all(&target.root)
+        .map_err(|err| format!("Failed to create {}: {}", target.root.display(), err))?;
+
+    let pattern = payload
+        .pattern
+        .as_deref()
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(glob::Pattern::new)
+        .transpose()
+        .map_err(|err| format!("Invalid skill pattern: {}", err))?;
+    let select_all = payload.skill_ids.is_empty() && pattern.is_none();
+
+    let source_root = fs::canonicalize(&source.root)
+        .map_err(|err| format!("Failed to resolve source root: {}", err))?;
+    let target_root = fs::canonicalize(&target.root)
+        .map_err(|err| format!("Failed to resolve target root: {}", err))?;
+    if source_root.starts_with(&target_root) || target_root.starts_with(&source_root) {
+        return Err("Source and target skill directories overlap".to_string());
+    }
+    let skills = read_skills(source).into_iter().filter(|skill| {
+        select_all
+            || payload.skill_ids.contains(&skill.id)
+            || pattern
+                .as_ref()
+                .is_some_and(|pattern| pattern.matches(&skill.id))
+    });
+    let mut added_ids = Vec::new();
+    let mut skipped_ids = Vec::new();
+    let mut updated_ids = Vec::new();
+
+    for skill in skills {
+        let skill_dir = source.root.join(&skill.id);
+        let skill_canon = fs::canonicalize(&skill_dir)
+            .map_err(|err| format!("Failed to resolve skill: {}", err))?;
+        if !skill_canon.starts_with(&source_root) {
+            return Err("Refusing to copy outside agent root".to_string());
+        }
+
+        let target_dir = target.root.join(&skill.id);
+        if target_dir.exists() {
+            if payload.incremental && skill_dir.is_dir() && target_dir.is_dir() {
+                let stats = copy_dir_incremental(&skill_dir, &target_dir, payload.mirror)?;
+                if stats.copied + stats.removed > 0 {
+                    updated_ids.push(skill.id);
+                    continue;
+                }
+            }
+            skipped_ids.push(skill.id);
+            continue;
         }
+        copy_dir_recursive(&skill_dir, &target_dir)?;
+        added_ids.push(skill.id);
     }
 
-    Ok(SkillTreeNode {
-        name,
-        path: path.display().to_string(),
-        kind: kind.to_string(),
-        children,
+    Ok(SyncResult {
+        added: added_ids.len(),
+        skipped: skipped_ids.len(),
+        updated: updated_ids.len(),
+        added_ids,
+        skipped_ids,
+        updated_ids,
     })
 }
 
-fn slugify(name: &str) -> String {
-    let mut slug = String::new();
-    let mut last_dash = false;
-
-    for ch in name.chars() {
-        if ch.is_ascii_alphanumeric() {
-            slug.push(ch.to_ascii_lowercase());
-            last_dash = false;
-        } else if !last_dash {
-            slug.push('-');
-            last_dash = true;
-        }
+#[tauri::command]
+fn sync_skills_to_all_sources(
+    payload: SyncSkillsAllInput,
+) -> Result<HashMap<String, SourceSyncResult>, String> {
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    if !sources.iter().any(|source| source.id == payload.source_id) {
+        return Err("Unknown skill source".to_string());
     }
 
-    let trimmed = slug.trim_matches('-').to_string();
-    if trimmed.is_empty() {
-        let stamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|duration| duration.as_secs())
-            .unwrap_or(0);
-        return format!("skill-{}", stamp);
+    let mut results = HashMap::new();
+    for target_id in expand_source_ids(&[ALL_SOURCES.to_string()], &sources) {
+        if target_id == payload.source_id {
+            continue;
+        }
+        let result = sync_skills_from_agent(SyncSkillsAgentsInput {
+            source_id: payload.source_id.clone(),
+            target_id: target_id.clone(),
+            skill_ids: payload.skill_ids.clone(),
+            pattern: payload.pattern.clone(),
+            incremental: payload.incremental,
+            mirror: payload.mirror,
+        });
+        results.insert(
+            target_id,
+            match result {
+                Ok(result) => SourceSyncResult {
+                    result: Some(result),
+                    error: None,
+                },
+                Err(err) => SourceSyncResult {
+                    result: None,
+                    error: Some(err),
+                },
+            },
+        );
     }
-    trimmed
+    Ok(results)
 }
 
-fn parse_skill_urls(input: &str, core_file: &str) -> Result<Vec<String>, String> {
-    let trimmed = input.trim();
-    if trimmed.is_empty() {
-        return Err("URL is required".to_string());
-    }
-    if !trimmed.starts_with("http://") && !trimmed.starts_with("https://") {
-        return Err("URL must start with http:// or https://".to_string());
+#[tauri::command]
+fn init_skills_dir(payload: SkillSourceInput) -> Result<String, String> {
+    let home = resolve_home()?;
+    let sources = source_configs(&home);
+    let source = sources
+        .iter()
+        .find(|source| source.id == payload.source_id)
+        .ok_or_else(|| "Unknown skill source".to_string())?;
+
+    if !source.install_root.is_dir() {
+        return Err("Agent is not installed".to_string());
     }
+    fs::create_dir_all(&source.root)
+        .map_err(|err| format!("Failed to create {}: {}", source.root.display(), err))?;
+    Ok(source.root.display().to_string())
+}
 
-    let parsed = Url::parse(trimmed).map_err(|_| "Invalid URL".to_string())?;
-    let host = parsed.host_str().unwrap_or("").trim_start_matches("www.");
-    let segments: Vec<&str> = parsed
-        .path()
-        .split('/')
-        .filter(|segment| !segment.is_empty())
+#[tauri::command]
+fn get_source_summary() -> Result<SourceSummary, String> {
+    let home = resolve_home()?;
+    let skill_sources = source_configs(&home)
+        .iter()
+        .map(|source| SkillSourceSummary {
+            id: source.id.to_string(),
+            label: source.label.to_string(),
+            installed: source.install_root.is_dir(),
+            skill_count: count_skills(source),
+        })
+        .collect();
+    let mcp_sources = mcp_source_configs(&home)
+        .iter()
+        .map(|config| McpSourceSummary {
+            id: config.id.to_string(),
+            label: config.label.to_string(),
+            exists: config.read_paths.iter().any(|path| path.exists())
+                || config.primary_path.exists(),
+            server_count: read_source_mcp_servers(config)
+                .map(|servers| servers.len())
+                .unwrap_or(0),
+        })
         .collect();
 
-    if host == "raw.githubusercontent.com" {
-        let mut url = trimmed.trim_end_matches('/').to_string();
-        if !url.ends_with(core_file) {
-            url.push('/');
-            url.push_str(core_file);
-        }
-        return Ok(vec![url]);
+    Ok(SourceSummary {
+        skill_sources,
+        mcp_sources,
+    })
+}
+
+#[tauri::command]
+fn list_mcp_sources(payload: Option<ListMcpSourcesInput>) -> Result<Vec<McpSource>, String> {
+    let home = resolve_home()?;
+    let settings = read_app_settings(&home);
+    let mut configs: Vec<McpSourceConfig> = mcp_source_configs(&home)
+        .into_iter()
+        .filter(|config| source_enabled(&settings, config.id))
+        .collect();
+    if let Some(project_root) = payload.and_then(|payload| payload.project_root) {
+        configs.push(project_mcp_config(&project_root)?);
     }
+    let mut response = Vec::new();
 
-    if host == "github.com" {
-        if segments.len() < 2 {
-            return Err("GitHub URL must include owner and repo".to_string());
+    for config in configs {
+        let has_config =
+            config.read_paths.iter().any(|path| path.exists()) || config.primary_path.exists();
+        if !config.install_root.is_dir() && !has_config {
+            continue;
         }
-        let owner = segments[0];
-        let repo = segments[1].trim_end_matches(".git");
-
-        if segments.len() >= 4 && (segments[2] == "tree" || segments[2] == "blob") {
-            let branch = segments[3];
-            let subpath = segments[4..].join("/");
-            let file_path = if subpath.is_empty() {
-                core_file.to_string()
-            } else if subpath.ends_with(core_file) {
-                subpath
+        let merged = merges_read_paths(&config);
+        let (path, exists, mut servers, mut warnings) = if merged {
+            let servers = read_merged_mcp_servers(&config)?;
+            (config.primary_path.clone(), has_config, servers, vec![])
+        } else {
+            let path = resolve_read_path(&config);
+            let exists = path.exists();
+            let servers = if exists {
+                read_mcp_servers(&config, &path)?
             } else {
-                format!("{}/{}", subpath, core_file)
+                vec![]
             };
-            let url = format!(
-                "https://raw.githubusercontent.com/{}/{}/{}/{}",
-                owner, repo, branch, file_path
-            );
-            return Ok(vec![url]);
-        }
-
-        let subpath = if segments.len() > 2 {
-            segments[2..].join("/")
-        } else {
-            String::new()
+            let warnings = read_path_warnings(&config, &path, &servers);
+            (path, exists, servers, warnings)
         };
-        let file_path = if subpath.is_empty() {
-            core_file.to_string()
-        } else if subpath.ends_with(core_file) {
-            subpath
-        } else {
-            format!("{}/{}", subpath, core_file)
+        let inputs = match read_mcp_inputs(&config, &path) {
+            Ok(inputs) => inputs,
+            Err(err) => {
+                warnings.push(err);
+                vec![]
+            }
         };
-        return Ok(vec![
-            format!(
-                "https://raw.githubusercontent.com/{}/{}/main/{}",
-                owner, repo, file_path
-            ),
-            format!(
-                "https://raw.githubusercontent.com/{}/{}/master/{}",
-                owner, repo, file_path
-            ),
-        ]);
-    }
+        let config_dir = path.parent().unwrap_or(&config.install_root);
+        let env_file_keys = env_file_keys(&mut servers, config_dir, &mut warnings);
 
-    let mut url = trimmed.trim_end_matches('/').to_string();
-    if !url.ends_with(core_file) {
-        url.push('/');
-        url.push_str(core_file);
+        response.push(McpSource {
+            id: config.id.to_string(),
+            label: config.label.to_string(),
+            path: path.display().to_string(),
+            format: config.format.to_string(),
+            exists,
+            servers,
+            warnings,
+            read_only: merged,
+            inputs,
+            env_file_keys,
+        });
     }
-    Ok(vec![url])
-}
 
-fn read_skill_source_url(skill_dir: &Path) -> Option<String> {
-    let path = skill_dir.join(SKILL_SOURCE_FILENAME);
-    let content = fs::read_to_string(path).ok()?;
-    let value: JsonValue = serde_json::from_str(&content).ok()?;
-    value
-        .get("url")
-        .and_then(|item| item.as_str())
-        .map(|item| item.to_string())
+    Ok(response)
 }
 
-fn write_skill_source_url(skill_dir: &Path, url: &str) -> Result<(), String> {
-    let trimmed = url.trim();
-    if trimmed.is_empty() {
-        return Ok(());
+fn mcp_server_transport(config: &JsonValue) -> Option<&'static str> {
+    let text = |key: &str| {
+        config
+            .get(key)
+            .and_then(|item| item.as_str())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    let remote = || {
+        let is_sse = text(SSE_MESSAGE_URL_KEY).is_some()
+            || text("url").is_some_and(|url| {
+                url.split(['?', '#'])
+                    .next()
+                    .unwrap_or(url)
+                    .trim_end_matches('/')
+                    .ends_with("/sse")
+            });
+        if is_sse {
+            "sse"
+        } else {
+            "http"
+        }
+    };
+    match text("type")
+        .map(|value| value.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("stdio") | Some("local") => Some("stdio"),
+        Some("sse") => Some("sse"),
+        Some("http") | Some("streamable-http") | Some("streamablehttp") => Some("http"),
+        Some("remote") => Some(remote()),
+        _ if text("command").is_some() => Some("stdio"),
+        _ if text("url").is_some() => Some(remote()),
+        _ => None,
     }
-    let path = skill_dir.join(SKILL_SOURCE_FILENAME);
-    let mut map = JsonMap::new();
-    map.insert("url".to_string(), JsonValue::String(trimmed.to_string()));
-    let content = serde_json::to_string_pretty(&JsonValue::Object(map))
-        .map_err(|err| format!("Failed to serialize JSON: {}", err))?;
-    fs::write(&path, format!("{}\n", content))
-        .map_err(|err| format!("Failed to write {}: {}", path.display(), err))?;
-    Ok(())
 }
 
-fn parse_github_location(input: &str) -> Result<GithubLocation, String> {
-    let trimmed = input.trim();
-    let parsed = Url::parse(trimmed).map_err(|_| "Invalid URL".to_string())?;
-    let host = parsed.host_str().unwrap_or("").trim_start_matches("www.");
-    let segments: Vec<&str> = parsed
-        .path()
-        .split('/')
-        .filter(|segment| !segment.is_empty())
-        .collect();
-    if host == "raw.githubusercontent.com" {
-        if segments.len() < 3 {
-            return Err("Raw GitHub URL must include owner/repo/branch".to_string());
-        }
-        let owner = segments[0].to_string();
-        let repo = segments[1].trim_end_matches(".git").to_string();
-        let branch = Some(segments[2].to_string());
-        let path = if segments.len() > 3 {
-            segments[3..].join("/")
-        } else {
-            String::new()
-        };
-        return Ok(GithubLocation {
-            owner,
-            repo,
-            branch,
-            path,
-        });
+#[tauri::command]
+fn list_mcp_servers_from_path(payload: McpPathInput) -> Result<Vec<McpServer>, String> {
+    let config = custom_mcp_config(&payload.path, &payload.kind)?;
+    if !config.primary_path.is_file() {
+        return Err(format!("{} does not exist", config.primary_path.display()));
     }
+    read_mcp_servers(&config, &config.primary_path)
+}
 
-    if host != "github.com" {
-        return Err("Not a GitHub URL".to_string());
+#[tauri::command]
+fn upsert_mcp_servers_to_path(payload: UpsertMcpPathInput) -> Result<Option<String>, String> {
+    let config = custom_mcp_config(&payload.path, &payload.kind)?;
+    if config.primary_path.is_dir() {
+        return Err(format!("{} is a directory", config.primary_path.display()));
     }
-    if segments.len() < 2 {
-        return Err("GitHub URL must include owner and repo".to_string());
+    if let Some(parent) = config.primary_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|err| format!("Failed to create {}: {}", parent.display(), err))?;
     }
 
-    let owner = segments[0].to_string();
-    let repo = segments[1].trim_end_matches(".git").to_string();
+    let servers = parse_mcp_json(&payload.json)?;
+    validate_mcp_servers(&servers)?;
+    let inputs = parse_mcp_inputs(&payload.json)?;
+    let mut ids: Vec<String> = servers.keys().cloned().collect();
+    ids.sort();
+    let backup_path = backup_file(&config.primary_path)?;
+    let result = upsert_mcp_servers(&config, servers)
+        .and_then(|_| merge_mcp_inputs(&config, inputs))
+        .map(|_| backup_path.map(|path| path.display().to_string()));
+    record_history(
+        "upsert_mcp_servers",
+        &config.primary_path.display().to_string(),
+        &ids.join(", "),
+        None,
+        &result,
+    );
+    result
+}
 
-    if segments.len() >= 4 && (segments[2] == "tree" || segments[2] == "blob") {
-        let branch = Some(segments[3].to_string());
-        let mut path = segments[4..].join("/");
-        if segments[2] == "blob" {
-            if let Some((dir, _)) = path.rsplit_once('/') {
-                path = dir.to_string();
-            } else {
-                path.clear();
-            }
+#[tauri::command]
+fn list_mcp_servers_by_type(payload: McpTransportInput) -> Result<Vec<McpServerGroup>, String> {
+    let transport = payload.transport.trim().to_ascii_lowercase();
+    if !["stdio", "sse", "http"].contains(&transport.as_str()) {
+        return Err(format!("Unknown transport: {}", payload.transport));
+    }
+    let home = resolve_home()?;
+    let settings = read_app_settings(&home);
+    let mut groups = Vec::new();
+
+    for config in mcp_source_configs(&home) {
+        if !source_enabled(&settings, config.id) {
+            continue;
         }
-        return Ok(GithubLocation {
-            owner,
-            repo,
-            branch,
-            path,
+        let servers: Vec<McpServer> = read_source_mcp_servers(&config)?
+            .into_iter()
+            .filter(|server| mcp_server_transport(&server.config) == Some(transport.as_str()))
+            .collect();
+        if servers.is_empty() {
+            continue;
+        }
+        groups.push(McpServerGroup {
+            source_id: config.id.to_string(),
+            label: config.label.to_string(),
+            servers,
         });
     }
 
-    let path = if segments.len() > 2 {
-        segments[2..].join("/")
-    } else {
-        String::new()
-    };
-
-    Ok(GithubLocation {
-        owner,
-        repo,
-        branch: None,
-        path,
-    })
+    Ok(groups)
 }
 
-fn github_token() -> Option<String> {
-    let ui_token = TOKEN_OVERRIDE.with(|cell| cell.borrow().clone());
-    if let Some(token) = ui_token {
-        if !token.trim().is_empty() {
-            return Some(token);
-        }
+#[tauri::command]
+fn sync_mcp_from_agent(payload: SyncAgentsInput) -> Result<SyncResult, String> {
+    if payload.source_id == payload.target_id {
+        return Err("Source and target must be different".to_string());
     }
-    std::env::var("SKILL_GITHUB_TOKEN")
-        .ok()
-        .filter(|value| !value.trim().is_empty())
-        .or_else(|| {
-            std::env::var("GITHUB_TOKEN")
-                .ok()
-                .filter(|value| !value.trim().is_empty())
-        })
-        .or_else(|| {
-            std::env::var("GH_TOKEN")
-                .ok()
-                .filter(|value| !value.trim().is_empty())
-        })
-}
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let source = configs
+        .iter()
+        .find(|config| config.id == payload.source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())?;
+    let target = configs
+        .iter()
+        .find(|config| config.id == payload.target_id)
+        .ok_or_else(|| "Unknown MCP target".to_string())?;
 
-fn github_request(agent: &ureq::Agent, url: &str) -> ureq::Request {
-    let mut request = agent.get(url).set("Accept", "application/vnd.github+json");
-    if let Some(token) = github_token() {
-        request = request.set("Authorization", &format!("Bearer {}", token));
+    let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if canonical(&resolve_read_path(source)) == canonical(&target.primary_path) {
+        return Err("Source and target MCP configs are the same file".to_string());
     }
-    request
-}
 
-fn read_json_response(response: ureq::Response) -> Result<JsonValue, String> {
-    let body = response
-        .into_string()
-        .map_err(|err| format!("Failed to read response: {}", err))?;
-    serde_json::from_str(&body).map_err(|err| format!("Invalid JSON: {}", err))
-}
+    let source_servers = read_source_mcp_servers(source)?;
+    let target_servers = read_source_mcp_servers(target)?;
+    let existing_ids: HashSet<String> =
+        target_servers.into_iter().map(|server| server.id).collect();
 
-fn github_contents_url(owner: &str, repo: &str, path: &str, branch: &str) -> String {
-    if path.is_empty() {
-        format!(
-            "https://api.github.com/repos/{}/{}/contents?ref={}",
-            owner, repo, branch
-        )
-    } else {
-        format!(
-            "https://api.github.com/repos/{}/{}/contents/{}?ref={}",
-            owner, repo, path, branch
-        )
+    let mut added_ids = Vec::new();
+    let mut skipped_ids = Vec::new();
+    let mut to_insert = HashMap::new();
+
+    for server in source_servers {
+        if existing_ids.contains(&server.id) {
+            skipped_ids.push(server.id);
+            continue;
+        }
+        added_ids.push(server.id.clone());
+        to_insert.insert(server.id, server.config);
+    }
+    let result = SyncResult {
+        added: added_ids.len(),
+        skipped: skipped_ids.len(),
+        updated: 0,
+        added_ids,
+        skipped_ids,
+        updated_ids: Vec::new(),
+    };
+    if payload.dry_run {
+        return Ok(result);
+    }
+
+    let mut referenced = Vec::new();
+    for config in to_insert.values() {
+        collect_input_references(config, &mut referenced);
+    }
+    let source_inputs = read_mcp_inputs(source, &resolve_read_path(source))?;
+    let carried_inputs: Vec<JsonValue> = source_inputs
+        .into_iter()
+        .filter(|input| input_id(input).is_some_and(|id| referenced.iter().any(|item| item == id)))
+        .collect();
+
+    if to_insert.is_empty() {
+        return Ok(result);
     }
+    let written = upsert_mcp_servers(target, to_insert)
+        .and_then(|_| merge_mcp_inputs(target, carried_inputs));
+    record_history(
+        "sync_mcp_servers",
+        &payload.source_id,
+        &payload.target_id,
+        None,
+        &written,
+    );
+    written.map(|_| result)
 }
 
-fn fetch_github_default_branch(
-    agent: &ureq::Agent,
-    owner: &str,
-    repo: &str,
-) -> Result<String, String> {
-    let url = format!("https://api.github.com/repos/{}/{}", owner, repo);
-    let response = github_request(agent, &url)
-        .call()
-        .map_err(|err| format!("Failed to read GitHub repo info: {}", err))?;
-    let value =
-        read_json_response(response).map_err(|err| format!("Invalid GitHub response: {}", err))?;
-    value
-        .get("default_branch")
-        .and_then(|item| item.as_str())
-        .map(|item| item.to_string())
-        .ok_or_else(|| "Missing default_branch in GitHub response".to_string())
+fn set_mcp_server_enabled(kind: &McpKind, config: &mut JsonValue, enabled: bool) -> bool {
+    let Some(obj) = config.as_object_mut() else {
+        return false;
+    };
+    match kind {
+        McpKind::OpenCodeJson | McpKind::CodexToml => {
+            let current = obj.get("enabled").and_then(|item| item.as_bool()) != Some(false);
+            if current == enabled {
+                return false;
+            }
+            obj.insert("enabled".to_string(), JsonValue::Bool(enabled));
+        }
+        McpKind::ClaudeJson | McpKind::AntigravityJson => {
+            let current = obj.get("disabled").and_then(|item| item.as_bool()) != Some(true);
+            if current == enabled {
+                return false;
+            }
+            if enabled {
+                obj.remove("disabled");
+            } else {
+                obj.insert("disabled".to_string(), JsonValue::Bool(true));
+            }
+        }
+    }
+    true
 }
 
-fn github_branch_candidates(agent: &ureq::Agent, location: &GithubLocation) -> Vec<String> {
-    if let Some(branch) = &location.branch {
-        return vec![branch.clone()];
-    }
-    let mut branches = Vec::new();
-    if let Ok(default_branch) = fetch_github_default_branch(agent, &location.owner, &location.repo)
-    {
-        branches.push(default_branch);
-    }
-    for candidate in ["main", "master"] {
-        if !branches.iter().any(|branch| branch == candidate) {
-            branches.push(candidate.to_string());
+#[tauri::command]
+fn set_all_mcp_servers_enabled(payload: SetAllMcpEnabledInput) -> Result<McpToggleResult, String> {
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let config = configs
+        .iter()
+        .find(|config| config.id == payload.source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())?;
+
+    let write_config = mcp_write_config(config, payload.target_path.as_deref())?;
+    let mut changed = HashMap::new();
+    let mut unchanged = 0;
+    for mut server in read_mcp_servers(config, &write_config.primary_path)? {
+        if set_mcp_server_enabled(&config.kind, &mut server.config, payload.enabled) {
+            changed.insert(server.id, server.config);
+        } else {
+            unchanged += 1;
         }
     }
-    if branches.is_empty() {
-        branches.push("main".to_string());
+
+    let mut backup_path = None;
+    let changed_count = changed.len();
+    if !changed.is_empty() {
+        let mut ids: Vec<String> = changed.keys().cloned().collect();
+        ids.sort();
+        backup_path = backup_file(&write_config.primary_path)?;
+        let written = upsert_mcp_servers(&write_config, changed);
+        record_history(
+            if payload.enabled {
+                "enable_mcp_servers"
+            } else {
+                "disable_mcp_servers"
+            },
+            &payload.source_id,
+            &ids.join(", "),
+            None,
+            &written,
+        );
+        written?;
     }
-    branches
+
+    Ok(McpToggleResult {
+        changed: changed_count,
+        unchanged,
+        backup_path: backup_path.map(|path| path.display().to_string()),
+    })
 }
 
-fn fetch_github_contents(
-    agent: &ureq::Agent,
-    owner: &str,
-    repo: &str,
-    path: &str,
-    branch: &str,
-) -> Result<Vec<GithubContentEntry>, String> {
-    let url = github_contents_url(owner, repo, path, branch);
-    let response = github_request(agent, &url)
-        .call()
-        .map_err(|err| format!("Failed to read GitHub contents: {}", err))?;
-    let value =
-        read_json_response(response).map_err(|err| format!("Invalid GitHub response: {}", err))?;
-    match value {
-        JsonValue::Array(items) => items
-            .into_iter()
-            .map(|item| {
-                serde_json::from_value(item).map_err(|err| format!("Invalid GitHub entry: {}", err))
-            })
-            .collect(),
-        JsonValue::Object(_) => {
-            let entry: GithubContentEntry = serde_json::from_value(value)
-                .map_err(|err| format!("Invalid GitHub entry: {}", err))?;
-            Ok(vec![entry])
+fn merge_json_values(primary: &JsonValue, secondary: &JsonValue) -> JsonValue {
+    match (primary, secondary) {
+        (JsonValue::Object(primary), JsonValue::Object(secondary)) => {
+            let mut merged = secondary.clone();
+            for (key, value) in primary {
+                let value = match secondary.get(key) {
+                    Some(other) => merge_json_values(value, other),
+                    None => value.clone(),
+                };
+                merged.insert(key.clone(), value);
+            }
+            JsonValue::Object(merged)
         }
-        _ => Err("Unexpected GitHub response".to_string()),
+        (JsonValue::Array(primary), JsonValue::Array(secondary)) => {
+            let mut merged = primary.clone();
+            for item in secondary {
+                if !merged.contains(item) {
+                    merged.push(item.clone());
+                }
+            }
+            JsonValue::Array(merged)
+        }
+        (primary, _) => primary.clone(),
     }
 }
 
-fn decode_base64_payload(content: &str) -> Result<Vec<u8>, String> {
-    let cleaned = content.replace('\n', "");
-    base64::engine::general_purpose::STANDARD
-        .decode(cleaned.as_bytes())
-        .map_err(|err| format!("Failed to decode base64 payload: {}", err))
-}
+#[tauri::command]
+fn merge_mcp_server(payload: MergeMcpServerInput) -> Result<McpServer, String> {
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let find_config = |source_id: &str| {
+        configs
+            .iter()
+            .find(|config| config.id == source_id)
+            .ok_or_else(|| format!("Unknown MCP source {}", source_id))
+    };
+    let find_server = |config: &McpSourceConfig| -> Result<JsonValue, String> {
+        read_source_mcp_servers(config)?
+            .into_iter()
+            .find(|server| server.id == payload.id)
+            .map(|server| server.config)
+            .ok_or_else(|| format!("MCP server {} not found in {}", payload.id, config.id))
+    };
 
-fn fetch_github_blob_content(
-    agent: &ureq::Agent,
-    owner: &str,
-    repo: &str,
-    sha: &str,
-) -> Result<Vec<u8>, String> {
-    let url = format!(
-        "https://api.github.com/repos/{}/{}/git/blobs/{}",
-        owner, repo, sha
+    let a = find_server(find_config(&payload.a_source_id)?)?;
+    let b = find_server(find_config(&payload.b_source_id)?)?;
+    let target = mcp_write_config(find_config(&payload.target_source_id)?, None)?;
+    let merged = match payload.prefer.as_deref().unwrap_or("a") {
+        "a" => merge_json_values(&a, &b),
+        "b" => merge_json_values(&b, &a),
+        other => return Err(format!("Unknown prefer value: {}", other)),
+    };
+
+    let written = upsert_mcp_servers(
+        &target,
+        HashMap::from([(payload.id.clone(), merged.clone())]),
     );
-    let response = github_request(agent, &url)
-        .call()
-        .map_err(|err| format!("Failed to read GitHub blob: {}", err))?;
-    let value =
-        read_json_response(response).map_err(|err| format!("Invalid GitHub response: {}", err))?;
-    let obj = value
-        .as_object()
-        .ok_or_else(|| "Invalid GitHub blob response".to_string())?;
-    let content = obj
-        .get("content")
-        .and_then(|item| item.as_str())
-        .ok_or_else(|| "Missing content in GitHub blob response".to_string())?;
-    let encoding = obj
-        .get("encoding")
-        .and_then(|item| item.as_str())
-        .unwrap_or("base64");
-    if encoding != "base64" {
-        return Err("Unsupported GitHub blob encoding".to_string());
-    }
-    decode_base64_payload(content)
+    record_history(
+        "merge_mcp_server",
+        &payload.target_source_id,
+        &payload.id,
+        None,
+        &written,
+    );
+    written?;
+    Ok(McpServer {
+        id: payload.id,
+        config: merged,
+    })
 }
 
-fn fetch_github_file_content(
-    agent: &ureq::Agent,
-    owner: &str,
-    repo: &str,
-    path: &str,
-    branch: &str,
-) -> Result<Vec<u8>, String> {
-    let url = github_contents_url(owner, repo, path, branch);
-    let response = github_request(agent, &url)
-        .call()
-        .map_err(|err| format!("Failed to read GitHub file: {}", err))?;
-    let value =
-        read_json_response(response).map_err(|err| format!("Invalid GitHub response: {}", err))?;
-    let obj = value
-        .as_object()
-        .ok_or_else(|| "Invalid GitHub file response".to_string())?;
-    if let Some(content) = obj.get("content").and_then(|item| item.as_str()) {
-        let encoding = obj
-            .get("encoding")
-            .and_then(|item| item.as_str())
-            .unwrap_or("base64");
-        if encoding != "base64" {
-            return Err("Unsupported GitHub file encoding".to_string());
+#[tauri::command]
+fn migrate_mcp_servers(payload: MigrateMcpInput) -> Result<SyncResult, String> {
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let config = configs
+        .iter()
+        .find(|config| config.id == payload.source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())?;
+
+    let from = mcp_write_config(config, Some(&payload.from_path))?;
+    let to = mcp_write_config(config, Some(&payload.to_path))?;
+    if from.primary_path == to.primary_path {
+        return Err("Source and target files must be different".to_string());
+    }
+
+    let mut added_ids = Vec::new();
+    let mut skipped_ids = Vec::new();
+    let mut to_insert = HashMap::new();
+    let servers = read_mcp_servers(config, &from.primary_path)?;
+    for id in &payload.ids {
+        match servers.iter().find(|server| &server.id == id) {
+            Some(server) => {
+                to_insert.insert(server.id.clone(), server.config.clone());
+                added_ids.push(id.clone());
+            }
+            None => skipped_ids.push(id.clone()),
         }
-        return decode_base64_payload(content);
     }
-    if let Some(sha) = obj.get("sha").and_then(|item| item.as_str()) {
-        return fetch_github_blob_content(agent, owner, repo, sha);
+
+    let mut referenced = Vec::new();
+    for config in to_insert.values() {
+        collect_input_references(config, &mut referenced);
     }
-    Err("Missing content in GitHub file response".to_string())
-}
+    let carried_inputs: Vec<JsonValue> = read_mcp_inputs(config, &from.primary_path)?
+        .into_iter()
+        .filter(|input| input_id(input).is_some_and(|id| referenced.iter().any(|item| item == id)))
+        .collect();
 
-fn write_bytes_to_path(bytes: &[u8], dest_path: &Path) -> Result<(), String> {
-    if let Some(parent) = dest_path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|err| format!("Failed to create {}: {}", parent.display(), err))?;
+    if !to_insert.is_empty() {
+        let mut moved: Vec<String> = to_insert.keys().cloned().collect();
+        moved.sort();
+        let written = upsert_mcp_servers(&to, to_insert)
+            .and_then(|_| merge_mcp_inputs(&to, carried_inputs))
+            .and_then(|_| {
+                if payload.remove_from_source {
+                    for id in &moved {
+                        delete_mcp_server_for_source(&from, id)?;
+                    }
+                }
+                Ok(())
+            });
+        record_history(
+            "migrate_mcp_servers",
+            &payload.source_id,
+            &moved.join(", "),
+            None,
+            &written,
+        );
+        written?;
     }
-    fs::write(dest_path, bytes)
-        .map_err(|err| format!("Failed to write {}: {}", dest_path.display(), err))?;
-    Ok(())
+
+    Ok(SyncResult {
+        added: added_ids.len(),
+        skipped: skipped_ids.len(),
+        updated: 0,
+        added_ids,
+        skipped_ids,
+        updated_ids: Vec::new(),
+    })
 }
 
-fn download_github_directory(
-    agent: &ureq::Agent,
-    location: &GithubLocation,
-    branch: &str,
-    dest_dir: &Path,
-) -> Result<(), String> {
-    download_github_directory_recursive(
-        agent,
-        &location.owner,
-        &location.repo,
-        branch,
-        &location.path,
-        dest_dir,
-    )
+#[tauri::command]
+fn check_mcp_config_health(payload: McpSourceInput) -> Result<Vec<McpHealthIssue>, String> {
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let config = configs
+        .iter()
+        .find(|config| config.id == payload.source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())?;
+
+    let servers = read_source_mcp_servers(config)?;
+    let input_ids: HashSet<String> = read_mcp_inputs(config, &resolve_read_path(config))?
+        .iter()
+        .filter_map(|input| input_id(input).map(|id| id.to_string()))
+        .collect();
+    let agent = ureq::AgentBuilder::new()
+        .user_agent(&user_agent())
+        .timeout(Duration::from_secs(5))
+        .build();
+    let read_path = resolve_read_path(config);
+    let config_dir = read_path.parent().unwrap_or(&config.install_root);
+
+    Ok(servers
+        .iter()
+        .flat_map(|server| mcp_server_health(&agent, server, &input_ids, config_dir))
+        .collect())
 }
 
-fn download_github_directory_recursive(
-    agent: &ureq::Agent,
-    owner: &str,
-    repo: &str,
-    branch: &str,
-    repo_path: &str,
-    dest_dir: &Path,
-) -> Result<(), String> {
-    fs::create_dir_all(dest_dir)
-        .map_err(|err| format!("Failed to create {}: {}", dest_dir.display(), err))?;
-    let entries = fetch_github_contents(agent, owner, repo, repo_path, branch)?;
-    for entry in entries {
-        match entry.item_type.as_str() {
-            "dir" => {
-                let next_dest = dest_dir.join(&entry.name);
-                download_github_directory_recursive(
-                    agent,
-                    owner,
-                    repo,
-                    branch,
-                    &entry.path,
-                    &next_dest,
-                )?;
+const MCP_TEST_TIMEOUT_SECS: u64 = 10;
+const MCP_PROTOCOL_VERSION: &str = "2025-03-26";
+
+fn missing_input_definitions(
+    config: &JsonValue,
+    inputs: &[JsonValue],
+    provided: &HashMap<String, String>,
+) -> Vec<JsonValue> {
+    let mut references = Vec::new();
+    collect_input_references(config, &mut references);
+    references
+        .into_iter()
+        .filter(|reference| !provided.contains_key(reference))
+        .map(|reference| {
+            inputs
+                .iter()
+                .find(|input| input_id(input) == Some(reference.as_str()))
+                .cloned()
+                .unwrap_or_else(|| serde_json::json!({ "id": reference, "type": "promptString" }))
+        })
+        .collect()
+}
+
+fn substitute_input_values(value: &mut JsonValue, provided: &HashMap<String, String>) {
+    match value {
+        JsonValue::String(text) => {
+            for (id, replacement) in provided {
+                let pattern = format!("${{input:{}}}", id);
+                if text.contains(&pattern) {
+                    *text = text.replace(&pattern, replacement);
+                }
             }
-            "file" => {
-                let dest_path = dest_dir.join(&entry.name);
-                let bytes = if let Some(sha) = entry.sha.as_deref() {
-                    fetch_github_blob_content(agent, owner, repo, sha)?
-                } else {
-                    fetch_github_file_content(agent, owner, repo, &entry.path, branch)?
-                };
-                write_bytes_to_path(&bytes, &dest_path)?;
+        }
+        JsonValue::Array(items) => {
+            for item in items {
+                substitute_input_values(item, provided);
+            }
+        }
+        JsonValue::Object(map) => {
+            for item in map.values_mut() {
+                substitute_input_values(item, provided);
             }
-            _ => {}
         }
+        _ => {}
     }
-    Ok(())
 }
 
-fn github_file_path(location: &GithubLocation, core_file: &str) -> String {
-    let mut file_path = location.path.clone();
-    if file_path.is_empty() {
-        file_path = core_file.to_string();
-    } else if !file_path.ends_with(core_file) {
-        file_path = format!("{}/{}", file_path, core_file);
+fn mcp_initialize_request() -> JsonValue {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": MCP_PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "Ananke", "version": env!("CARGO_PKG_VERSION") }
+        }
+    })
+}
+
+fn mcp_initialize_result(response: &JsonValue) -> Result<Option<JsonValue>, String> {
+    if let Some(error) = response.get("error") {
+        let message = error
+            .get("message")
+            .and_then(|item| item.as_str())
+            .unwrap_or("unknown error");
+        return Err(format!("Server rejected initialize: {}", message));
     }
-    file_path
+    let result = response
+        .get("result")
+        .ok_or_else(|| "Initialize response has no result".to_string())?;
+    Ok(result.get("serverInfo").cloned())
 }
 
-fn fallback_name_from_url(input: &str, core_file: &str) -> String {
-    if let Ok(parsed) = Url::parse(input) {
-        let mut segments: Vec<&str> = parsed
-            .path()
-            .split('/')
-            .filter(|segment| !segment.is_empty())
-            .collect();
-        if let Some(last) = segments.pop() {
-            if last.eq_ignore_ascii_case(core_file) {
-                if let Some(prev) = segments.pop() {
-                    return prev.to_string();
-                }
+fn parse_initialize_body(body: &str) -> Result<Option<JsonValue>, String> {
+    let candidates = std::iter::once(body.trim()).chain(
+        body.lines()
+            .filter_map(|line| line.trim().strip_prefix("data:"))
+            .map(str::trim),
+    );
+    for candidate in candidates {
+        if let Ok(response) = serde_json::from_str::<JsonValue>(candidate) {
+            if response.get("id") == Some(&JsonValue::from(1)) {
+                return mcp_initialize_result(&response);
             }
-            return last.to_string();
         }
     }
-    "skill".to_string()
+    Err("Server did not answer initialize".to_string())
 }
 
-fn fetch_skill_content(urls: Vec<String>) -> Result<String, String> {
-    let agent = ureq::AgentBuilder::new().user_agent("Ananke/0.1").build();
-    let mut last_error = None;
-
-    for url in urls {
-        match agent.get(&url).call() {
-            Ok(response) => {
-                if response.status() == 200 {
-                    let body = response
-                        .into_string()
-                        .map_err(|err| format!("Failed to read response: {}", err))?;
-                    if body.trim().is_empty() {
-                        return Err("SKILL.md is empty".to_string());
+fn string_map(config: &JsonValue, key: &str) -> Vec<(String, String)> {
+    config
+        .get(key)
+        .and_then(|item| item.as_object())
+        .map(|map| {
+            map.iter()
+                .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn test_stdio_mcp_server(
+    config: &JsonValue,
+    env_file: Vec<(String, String)>,
+) -> Result<Option<JsonValue>, String> {
+    let command = config
+        .get("command")
+        .and_then(|item| item.as_str())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| "Server has no command".to_string())?;
+    let program = find_command_on_path(command)
+        .ok_or_else(|| format!("Command {} not found on PATH", command))?;
+    let args: Vec<String> = config
+        .get("args")
+        .and_then(|item| item.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut process = std::process::Command::new(program);
+    process
+        .args(&args)
+        .envs(env_file)
+        .envs(string_map(config, "env"))
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null());
+    if let Some(cwd) = config.get("cwd").and_then(|item| item.as_str()) {
+        process.current_dir(cwd);
+    }
+    let mut child = process
+        .spawn()
+        .map_err(|err| format!("Failed to start {}: {}", command, err))?;
+
+    let mut stdin = child.stdin.take();
+    let stdout = child.stdout.take();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    if let Some(stdout) = stdout {
+        std::thread::spawn(move || {
+            use std::io::BufRead;
+            for line in std::io::BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if let Ok(response) = serde_json::from_str::<JsonValue>(&line) {
+                    if response.get("id") == Some(&JsonValue::from(1)) {
+                        let _ = sender.send(response);
+                        break;
                     }
-                    return Ok(body);
                 }
-                last_error = Some(format!("Unexpected status {}", response.status()));
-            }
-            Err(err) => {
-                last_error = Some(format!("Request failed: {}", err));
             }
-        }
+        });
     }
+    let written = stdin
+        .as_mut()
+        .map(|stdin| writeln!(stdin, "{}", mcp_initialize_request()))
+        .unwrap_or(Ok(()));
+    let response = match written {
+        Ok(()) => receiver
+            .recv_timeout(Duration::from_secs(MCP_TEST_TIMEOUT_SECS))
+            .ok(),
+        Err(_) => None,
+    };
+    drop(stdin);
+    let _ = child.kill();
+    let _ = child.wait();
 
-    Err(last_error.unwrap_or_else(|| "Unable to download SKILL.md".to_string()))
+    let response = response.ok_or_else(|| {
+        format!(
+            "{} did not answer initialize within {} seconds",
+            command, MCP_TEST_TIMEOUT_SECS
+        )
+    })?;
+    mcp_initialize_result(&response)
 }
 
-fn line_col_from_index(input: &str, index: usize) -> (usize, usize) {
-    let mut line = 1usize;
-    let mut col = 1usize;
+fn test_remote_mcp_server(config: &JsonValue) -> Result<Option<JsonValue>, String> {
+    ensure_online()?;
+    let url = config
+        .get("url")
+        .and_then(|item| item.as_str())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| "Server has no url".to_string())?;
+    let agent = ureq::AgentBuilder::new()
+        .user_agent(&user_agent())
+        .timeout(Duration::from_secs(MCP_TEST_TIMEOUT_SECS))
+        .build();
+    let headers: Vec<(String, String)> = string_map(config, "headers")
+        .into_iter()
+        .chain(string_map(config, "http_headers"))
+        .collect();
 
-    for (offset, ch) in input.char_indices() {
-        if offset >= index {
-            break;
+    if mcp_server_transport(config) == Some("sse") {
+        let mut request = agent.get(url).set("Accept", "text/event-stream");
+        for (name, value) in &headers {
+            request = request.set(name, value);
         }
-        if ch == '\n' {
-            line += 1;
-            col = 1;
-        } else if ch != '\r' {
-            col += 1;
+        let response = request
+            .call()
+            .map_err(|err| format!("Failed to open {}: {}", url, err))?;
+        if !response.content_type().starts_with("text/event-stream") {
+            return Err(format!("{} did not open an event stream", url));
         }
+        return Ok(None);
     }
 
-    (line, col)
+    let mut request = agent
+        .post(url)
+        .set("Accept", "application/json, text/event-stream");
+    for (name, value) in &headers {
+        request = request.set(name, value);
+    }
+    let response = request
+        .set("Content-Type", "application/json")
+        .send_string(&mcp_initialize_request().to_string())
+        .map_err(|err| format!("Failed to initialize {}: {}", url, err))?;
+    let mut body = String::new();
+    response
+        .into_reader()
+        .take(MAX_SKILL_ARCHIVE_BYTES)
+        .read_to_string(&mut body)
+        .map_err(|err| format!("Failed to read response: {}", err))?;
+    parse_initialize_body(&body)
 }
 
-fn load_toml_value(path: &Path) -> Result<TomlValue, String> {
-    if !path.exists() {
-        return Ok(TomlValue::Table(TomlMap::new()));
-    }
-    let content = fs::read_to_string(path)
-        .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
-    if content.trim().is_empty() {
-        return Ok(TomlValue::Table(TomlMap::new()));
+#[tauri::command]
+fn test_mcp_server(payload: TestMcpServerInput) -> Result<McpTestResult, String> {
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let config = configs
+        .iter()
+        .find(|config| config.id == payload.source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())?;
+    let server = read_source_mcp_servers(config)?
+        .into_iter()
+        .find(|server| server.id == payload.id)
+        .ok_or_else(|| format!("MCP server {} not found", payload.id))?;
+    let read_path = resolve_read_path(config);
+    let inputs = read_mcp_inputs(config, &read_path)?;
+
+    let missing_inputs = missing_input_definitions(&server.config, &inputs, &payload.inputs);
+    if !missing_inputs.is_empty() {
+        return Ok(McpTestResult {
+            ok: false,
+            message: format!("{} input value(s) required", missing_inputs.len()),
+            missing_inputs,
+            server_info: None,
+        });
     }
-    content.parse::<TomlValue>().map_err(|err| {
-        let location = err
-            .span()
-            .map(|span| {
-                let (line, col) = line_col_from_index(&content, span.start);
-                format!("line {}, column {}", line, col)
-            })
-            .unwrap_or_else(|| "unknown location".to_string());
-        format!("Invalid TOML in {} ({}): {}", path.display(), location, err)
+
+    let mut resolved = server.config.clone();
+    substitute_input_values(&mut resolved, &payload.inputs);
+    let config_dir = read_path.parent().unwrap_or(&config.install_root);
+    let env_file = match server_env_file(&resolved, config_dir) {
+        Some(path) => load_env_file(&path)?,
+        None => vec![],
+    };
+    let outcome = match mcp_server_transport(&resolved) {
+        Some("stdio") => test_stdio_mcp_server(&resolved, env_file),
+        Some(_) => test_remote_mcp_server(&resolved),
+        None => Err("Server has neither a command nor a url".to_string()),
+    };
+    Ok(match outcome {
+        Ok(server_info) => McpTestResult {
+            ok: true,
+            message: format!("{} responded", payload.id),
+            missing_inputs: vec![],
+            server_info,
+        },
+        Err(message) => McpTestResult {
+            ok: false,
+            message,
+            missing_inputs: vec![],
+            server_info: None,
+        },
     })
 }
 
-fn save_toml_value(path: &Path, value: &TomlValue) -> Result<(), String> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|err| format!("Failed to create {}: {}", parent.display(), err))?;
+fn mcp_schema_fields(kind: &McpKind) -> Vec<McpSchemaField> {
+    let field =
+        |name: &str, value_type: &str, description: &str, allowed: &[&str]| McpSchemaField {
+            name: name.to_string(),
+            value_type: value_type.to_string(),
+            description: description.to_string(),
+            allowed_values: allowed.iter().map(|value| value.to_string()).collect(),
+        };
+    let mut fields = vec![
+        field(
+            "command",
+            "string",
+            "Executable for stdio servers; required unless url is set",
+            &[],
+        ),
+        field("args", "string[]", "Arguments passed to command", &[]),
+        field("env", "object", "Environment variables for command", &[]),
+        field(
+            "envFile",
+            "string",
+            "Path to a .env file, relative to the config file",
+            &[],
+        ),
+        field(
+            "url",
+            "string",
+            "Endpoint for remote servers; required unless command is set",
+            &[],
+        ),
+        field(
+            SSE_MESSAGE_URL_KEY,
+            "string",
+            "Separate POST endpoint for SSE servers",
+            &[],
+        ),
+    ];
+    match kind {
+        McpKind::ClaudeJson => {
+            fields.push(field(
+                "type",
+                "string",
+                "Transport",
+                &["stdio", "sse", "http"],
+            ));
+            fields.push(field("headers", "object", "HTTP headers for url", &[]));
+            fields.push(field("disabled", "boolean", "Turns the server off", &[]));
+        }
+        McpKind::AntigravityJson => {
+            fields.push(field("headers", "object", "HTTP headers for url", &[]));
+            fields.push(field("disabled", "boolean", "Turns the server off", &[]));
+        }
+        McpKind::OpenCodeJson => {
+            fields.push(field(
+                "type",
+                "string",
+                "Derived from command or url when omitted",
+                &["local", "remote"],
+            ));
+            fields.push(field("headers", "object", "HTTP headers for url", &[]));
+            fields.push(field(
+                "enabled",
+                "boolean",
+                "Turns the server on or off",
+                &[],
+            ));
+        }
+        McpKind::CodexToml => {
+            fields.push(field("http_headers", "object", "HTTP headers for url", &[]));
+            fields.push(field(
+                "enabled",
+                "boolean",
+                "Turns the server on or off",
+                &[],
+            ));
+        }
     }
-    let content = toml::to_string_pretty(value)
-        .map_err(|err| format!("Failed to serialize TOML: {}", err))?;
-    fs::write(path, content).map_err(|err| format!("Failed to write {}: {}", path.display(), err))
+    fields
 }
 
-fn load_json_value(path: &Path) -> Result<JsonValue, String> {
-    if !path.exists() {
-        return Ok(JsonValue::Object(JsonMap::new()));
-    }
-    let content = fs::read_to_string(path)
-        .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
-    if content.trim().is_empty() {
-        return Ok(JsonValue::Object(JsonMap::new()));
-    }
-    serde_json::from_str(&content).map_err(|err| {
-        format!(
-            "Invalid JSON in {} (line {}, column {}): {}",
-            path.display(),
-            err.line(),
-            err.column(),
-            err
-        )
+#[tauri::command]
+fn get_mcp_schema(payload: McpSourceInput) -> Result<McpSchema, String> {
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let config = configs
+        .iter()
+        .find(|config| config.id == payload.source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())?;
+
+    Ok(McpSchema {
+        format: config.format.to_string(),
+        fields: mcp_schema_fields(&config.kind),
     })
 }
 
-fn save_json_value(path: &Path, value: &JsonValue) -> Result<(), String> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|err| format!("Failed to create {}: {}", parent.display(), err))?;
-    }
-    let content = serde_json::to_string_pretty(value)
-        .map_err(|err| format!("Failed to serialize JSON: {}", err))?;
-    fs::write(path, format!("{}\n", content))
-        .map_err(|err| format!("Failed to write {}: {}", path.display(), err))
+#[tauri::command]
+fn get_mcp_read_paths(payload: McpSourceInput) -> Result<McpReadPaths, String> {
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let config = configs
+        .iter()
+        .find(|config| config.id == payload.source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())?;
+
+    let selected = resolve_read_path(config);
+    Ok(McpReadPaths {
+        primary_path: config.primary_path.display().to_string(),
+        primary_path_exists: config.primary_path.exists(),
+        selected_path: selected.display().to_string(),
+        merged: merges_read_paths(config),
+        read_paths: config
+            .read_paths
+            .iter()
+            .map(|path| McpReadPathEntry {
+                path: path.display().to_string(),
+                exists: path.exists(),
+                selected: *path == selected,
+            })
+            .collect(),
+    })
 }
 
-fn toml_to_json(value: &TomlValue) -> JsonValue {
-    match value {
-        TomlValue::String(value) => JsonValue::String(value.clone()),
-        TomlValue::Integer(value) => JsonValue::Number((*value).into()),
-        TomlValue::Float(value) => {
-            JsonValue::Number(serde_json::Number::from_f64(*value).unwrap_or_else(|| 0.into()))
-        }
-        TomlValue::Boolean(value) => JsonValue::Bool(*value),
-        TomlValue::Datetime(value) => JsonValue::String(value.to_string()),
-        TomlValue::Array(values) => JsonValue::Array(values.iter().map(toml_to_json).collect()),
-        TomlValue::Table(table) => {
-            let mut map = JsonMap::new();
-            for (key, value) in table {
-                map.insert(key.clone(), toml_to_json(value));
-            }
-            JsonValue::Object(map)
-        }
-    }
+#[tauri::command]
+fn check_mcp_commands(payload: McpSourceInput) -> Result<Vec<McpCommandCheck>, String> {
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let config = configs
+        .iter()
+        .find(|config| config.id == payload.source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())?;
+
+    Ok(read_source_mcp_servers(config)?
+        .into_iter()
+        .filter_map(|server| {
+            let command = server.config.get("command")?.as_str()?.trim().to_string();
+            Some(McpCommandCheck {
+                found: find_command_on_path(&command).is_some(),
+                server_id: server.id,
+                command,
+            })
+        })
+        .collect())
 }
 
-fn json_to_toml(value: &JsonValue) -> Result<TomlValue, String> {
-    match value {
-        JsonValue::Null => Err("Null values are not supported".to_string()),
-        JsonValue::Bool(value) => Ok(TomlValue::Boolean(*value)),
-        JsonValue::Number(value) => {
-            if let Some(int) = value.as_i64() {
-                Ok(TomlValue::Integer(int))
-            } else if let Some(float) = value.as_f64() {
-                Ok(TomlValue::Float(float))
-            } else {
-                Err("Unsupported number".to_string())
+#[tauri::command]
+fn normalize_mcp_config(payload: McpSourceInput) -> Result<Vec<NormalizedMcpFile>, String> {
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let config = configs
+        .iter()
+        .find(|config| config.id == payload.source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())?;
+
+    let paths: Vec<PathBuf> = if merges_read_paths(config) {
+        let mut paths = vec![config.primary_path.clone()];
+        for path in &config.read_paths {
+            if !paths.contains(path) {
+                paths.push(path.clone());
             }
         }
-        JsonValue::String(value) => Ok(TomlValue::String(value.clone())),
-        JsonValue::Array(values) => {
-            let mut array = Vec::new();
-            for value in values {
-                array.push(json_to_toml(value)?);
-            }
-            Ok(TomlValue::Array(array))
+        paths.into_iter().filter(|path| path.exists()).collect()
+    } else {
+        let path = resolve_read_path(config);
+        if path.exists() {
+            vec![path]
+        } else {
+            vec![]
         }
-        JsonValue::Object(values) => {
-            let mut table = TomlMap::new();
-            for (key, value) in values {
-                table.insert(key.clone(), json_to_toml(value)?);
-            }
-            Ok(TomlValue::Table(table))
+    };
+
+    let mut results = Vec::new();
+    for path in paths {
+        let servers = read_mcp_servers(config, &path)?;
+        let normalized: HashMap<String, JsonValue> = servers
+            .iter()
+            .map(|server| {
+                (
+                    server.id.clone(),
+                    opencode_to_standard_config(&server.config),
+                )
+            })
+            .collect();
+        let backup_path = backup_file(&path)?;
+        if !normalized.is_empty() {
+            let mut write_config = config.clone();
+            write_config.primary_path = path.clone();
+            let written = upsert_mcp_servers(&write_config, normalized);
+            record_history(
+                "normalize_mcp_config",
+                &payload.source_id,
+                &path.display().to_string(),
+                None,
+                &written,
+            );
+            written?;
         }
+        results.push(NormalizedMcpFile {
+            path: path.display().to_string(),
+            backup_path: backup_path.map(|backup| backup.display().to_string()),
+            servers: servers.len(),
+        });
     }
-}
 
-fn parse_mcp_json(input: &str) -> Result<HashMap<String, JsonValue>, String> {
-    let value: JsonValue =
-        serde_json::from_str(input).map_err(|err| format!("Invalid MCP JSON: {}", err))?;
-    let servers = value
-        .get("mcpServers")
-        .and_then(|item| item.as_object())
-        .ok_or_else(|| "mcpServers object missing".to_string())?;
+    Ok(results)
+}
 
-    let mut results = HashMap::new();
-    for (id, config) in servers {
-        results.insert(id.to_string(), config.clone());
-    }
+#[tauri::command]
+fn upsert_mcp_server_json(payload: UpsertMcpJsonInput) -> Result<(), String> {
+    let home = resolve_home()?;
+    let config = find_mcp_config(&home, &payload.source_id, payload.project_root.as_deref())?;
 
-    Ok(results)
+    let write_config = mcp_write_config(&config, payload.target_path.as_deref())?;
+    let servers = parse_mcp_json(&payload.json)?;
+    validate_mcp_servers(&servers)?;
+    let inputs = parse_mcp_inputs(&payload.json)?;
+    let mut ids: Vec<String> = servers.keys().cloned().collect();
+    ids.sort();
+    let result = upsert_mcp_servers(&write_config, servers)
+        .and_then(|_| merge_mcp_inputs(&write_config, inputs));
+    record_history(
+        "upsert_mcp_servers",
+        &payload.source_id,
+        &ids.join(", "),
+        None,
+        &result,
+    );
+    result
 }
 
-fn opencode_to_standard_config(config: &JsonValue) -> JsonValue {
-    let Some(obj) = config.as_object() else {
-        return config.clone();
+#[tauri::command]
+fn validate_mcp_json(payload: ValidateMcpJsonInput) -> McpValidation {
+    let servers = match parse_mcp_json(&payload.json) {
+        Ok(servers) => servers,
+        Err(err) => {
+            return McpValidation {
+                valid: false,
+                servers: Vec::new(),
+                errors: vec![err],
+            };
+        }
     };
-    let mut out = JsonMap::new();
 
-    if let Some(url) = obj.get("url") {
-        out.insert("url".to_string(), url.clone());
+    let mut ids: Vec<String> = servers.keys().cloned().collect();
+    ids.sort();
+    let mut errors: Vec<String> = ids
+        .iter()
+        .filter_map(|id| {
+            validate_mcp_server_config(&servers[id])
+                .err()
+                .map(|err| format!("{}: {}", id, err))
+        })
+        .collect();
+    if let Err(err) = parse_mcp_inputs(&payload.json) {
+        errors.push(err);
     }
 
-    if let Some(command) = obj.get("command") {
-        match command {
-            JsonValue::Array(items) => {
-                if let Some(first) = items.first().and_then(|item| item.as_str()) {
-                    out.insert("command".to_string(), JsonValue::String(first.to_string()));
-                    let args: Vec<JsonValue> = items
-                        .iter()
-                        .skip(1)
-                        .filter_map(|item| {
-                            item.as_str()
-                                .map(|value| JsonValue::String(value.to_string()))
-                        })
-                        .collect();
-                    if !args.is_empty() {
-                        out.insert("args".to_string(), JsonValue::Array(args));
-                    }
-                }
-            }
-            JsonValue::String(value) => {
-                out.insert("command".to_string(), JsonValue::String(value.to_string()));
-            }
-            _ => {}
-        }
+    McpValidation {
+        valid: errors.is_empty(),
+        servers: ids,
+        errors,
     }
+}
 
-    if let Some(enabled) = obj.get("enabled") {
-        out.insert("enabled".to_string(), enabled.clone());
-    }
+#[tauri::command]
+fn preview_mcp_upsert(payload: PreviewMcpUpsertInput) -> Result<Vec<McpServer>, String> {
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let config = configs
+        .iter()
+        .find(|config| config.id == payload.source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())?;
 
-    if let Some(server_type) = obj.get("type") {
-        out.insert("type".to_string(), server_type.clone());
+    let servers = parse_mcp_json(&payload.json)?;
+    let mut preview = Vec::new();
+    for (id, config_value) in servers {
+        preview.push(McpServer {
+            id,
+            config: mcp_config_for_kind(&config.kind, &config_value)?,
+        });
     }
+    preview.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(preview)
+}
 
-    if let Some(environment) = obj.get("environment") {
-        out.insert("env".to_string(), environment.clone());
-    } else if let Some(env) = obj.get("env") {
-        out.insert("env".to_string(), env.clone());
-    }
+#[tauri::command]
+fn delete_mcp_server(payload: DeleteMcpInput) -> Result<(), String> {
+    let home = resolve_home()?;
+    let config = find_mcp_config(&home, &payload.source_id, payload.project_root.as_deref())?;
+
+    let write_config = mcp_write_config(&config, payload.target_path.as_deref())?;
+    let result = delete_mcp_server_for_source(&write_config, &payload.id);
+    record_history(
+        "delete_mcp_server",
+        &payload.source_id,
+        &payload.id,
+        None,
+        &result,
+    );
+    result
+}
 
-    for (key, value) in obj {
-        if ["command", "url", "enabled", "type", "env", "environment"].contains(&key.as_str()) {
-            continue;
+fn mcp_config_files(config: &McpSourceConfig) -> Vec<PathBuf> {
+    let mut paths = vec![config.primary_path.clone()];
+    for path in &config.read_paths {
+        if !paths.contains(path) {
+            paths.push(path.clone());
         }
-        out.insert(key.clone(), value.clone());
     }
-
-    JsonValue::Object(out)
+    paths
 }
 
-fn antigravity_to_standard_config(config: &JsonValue) -> JsonValue {
-    let Some(obj) = config.as_object() else {
-        return config.clone();
-    };
-    let mut out = JsonMap::new();
+#[tauri::command]
+fn snapshot_mcp_config(payload: McpSourceInput) -> Result<String, String> {
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let config = configs
+        .iter()
+        .find(|config| config.id == payload.source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())?;
 
-    for (key, value) in obj {
-        if key == "serverUrl" {
-            out.insert("url".to_string(), value.clone());
-        } else if key != "url" || !out.contains_key("url") {
-            out.insert(key.clone(), value.clone());
+    let root = mcp_snapshots_dir(&home, config.id);
+    let mut snapshot_id = unix_timestamp().to_string();
+    let mut suffix = 1;
+    while root.join(&snapshot_id).exists() {
+        snapshot_id = format!("{}-{}", unix_timestamp(), suffix);
+        suffix += 1;
+    }
+    let snapshot_dir = root.join(&snapshot_id);
+    fs::create_dir_all(&snapshot_dir)
+        .map_err(|err| format!("Failed to create {}: {}", snapshot_dir.display(), err))?;
+
+    let mut files = Vec::new();
+    for (index, path) in mcp_config_files(config).into_iter().enumerate() {
+        let existed = path.is_file();
+        if existed {
+            fs::copy(&path, snapshot_dir.join(index.to_string()))
+                .map_err(|err| format!("Failed to snapshot {}: {}", path.display(), err))?;
         }
+        files.push(McpSnapshotFile {
+            path: path.display().to_string(),
+            existed,
+        });
     }
-
-    JsonValue::Object(out)
+    let manifest = serde_json::to_string_pretty(&files)
+        .map_err(|err| format!("Failed to serialize JSON: {}", err))?;
+    let manifest_path = snapshot_dir.join("manifest.json");
+    fs::write(&manifest_path, manifest).map_err(|err| write_error("File", &manifest_path, err))?;
+    Ok(snapshot_id)
 }
 
-fn standard_to_opencode_config(config: &JsonValue) -> Result<JsonValue, String> {
-    let obj = config
-        .as_object()
-        .ok_or_else(|| "MCP server config must be an object".to_string())?;
-    let mut out = obj.clone();
+#[tauri::command]
+fn restore_mcp_snapshot(payload: RestoreMcpSnapshotInput) -> Result<Vec<String>, String> {
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let config = configs
+        .iter()
+        .find(|config| config.id == payload.source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())?;
 
-    if let Some(command) = obj.get("command") {
-        if let Some(command_str) = command.as_str() {
-            let mut command_list = Vec::new();
-            command_list.push(JsonValue::String(command_str.to_string()));
-            if let Some(args) = obj.get("args").and_then(|value| value.as_array()) {
-                for arg in args {
-                    if let Some(value) = arg.as_str() {
-                        command_list.push(JsonValue::String(value.to_string()));
-                    }
-                }
-            }
-            out.insert("command".to_string(), JsonValue::Array(command_list));
-        }
-        out.remove("args");
+    if safe_relative_path(&payload.snapshot_id).is_none_or(|path| path.components().count() != 1) {
+        return Err("Invalid snapshot id".to_string());
     }
+    let snapshot_dir = mcp_snapshots_dir(&home, config.id).join(&payload.snapshot_id);
+    let manifest = fs::read_to_string(snapshot_dir.join("manifest.json"))
+        .map_err(|_| format!("Snapshot {} not found", payload.snapshot_id))?;
+    let files: Vec<McpSnapshotFile> = serde_json::from_str(&manifest)
+        .map_err(|err| format!("Invalid snapshot manifest: {}", err))?;
+    let known = mcp_config_files(config);
+
+    let result = restore_snapshot_files(&snapshot_dir, &files, &known);
+    record_history(
+        "restore_mcp_snapshot",
+        &payload.source_id,
+        &payload.snapshot_id,
+        None,
+        &result,
+    );
+    result
+}
 
-    if let Some(env) = out.remove("env") {
-        if !out.contains_key("environment") {
-            out.insert("environment".to_string(), env);
+fn restore_snapshot_files(
+    snapshot_dir: &Path,
+    files: &[McpSnapshotFile],
+    known: &[PathBuf],
+) -> Result<Vec<String>, String> {
+    let mut restored = Vec::new();
+    for (index, file) in files.iter().enumerate() {
+        let path = PathBuf::from(&file.path);
+        if !known.contains(&path) {
+            return Err(format!("Snapshot refers to unexpected path {}", file.path));
         }
-    }
-
-    if !out.contains_key("type") {
-        if out.contains_key("url") {
-            out.insert("type".to_string(), JsonValue::String("remote".to_string()));
-        } else if out.contains_key("command") {
-            out.insert("type".to_string(), JsonValue::String("local".to_string()));
+        backup_file(&path)?;
+        if file.existed {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|err| format!("Failed to create {}: {}", parent.display(), err))?;
+            }
+            fs::copy(snapshot_dir.join(index.to_string()), &path)
+                .map_err(|err| format!("Failed to restore {}: {}", path.display(), err))?;
+        } else if path.is_file() {
+            fs::remove_file(&path)
+                .map_err(|err| format!("Failed to remove {}: {}", path.display(), err))?;
         }
+        restored.push(file.path.clone());
     }
+    Ok(restored)
+}
 
-    Ok(JsonValue::Object(out))
+fn mcp_server_fingerprint(config: &JsonValue) -> Option<String> {
+    let text = |key: &str| {
+        config
+            .get(key)
+            .and_then(|item| item.as_str())
+            .map(str::trim)
+    };
+    let command = text("command").unwrap_or_default();
+    let url = text("url")
+        .or_else(|| text("serverUrl"))
+        .map(|url| url.trim_end_matches('/'))
+        .unwrap_or_default();
+    if command.is_empty() && url.is_empty() {
+        return None;
+    }
+    let mut parts = vec![command];
+    if let Some(args) = config.get("args").and_then(|item| item.as_array()) {
+        parts.extend(args.iter().filter_map(|arg| arg.as_str()));
+    }
+    parts.push(url);
+    Some(
+        parts
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
 }
 
-fn standard_to_antigravity_config(config: &JsonValue) -> Result<JsonValue, String> {
-    let obj = config
-        .as_object()
-        .ok_or_else(|| "MCP server config must be an object".to_string())?;
-    let mut out = JsonMap::new();
+#[tauri::command]
+fn find_duplicate_mcp_servers(payload: McpSourceInput) -> Result<Vec<McpDuplicateGroup>, String> {
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let config = configs
+        .iter()
+        .find(|config| config.id == payload.source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())?;
 
-    for (key, value) in obj {
-        if key == "serverUrl" {
-            out.insert(key.clone(), value.clone());
-        } else if key != "url" {
-            out.insert(key.clone(), value.clone());
+    Ok(duplicate_mcp_groups(read_source_mcp_servers(config)?))
+}
+
+fn duplicate_mcp_groups(servers: Vec<McpServer>) -> Vec<McpDuplicateGroup> {
+    let mut groups: Vec<McpDuplicateGroup> = Vec::new();
+    for server in servers {
+        let Some(fingerprint) = mcp_server_fingerprint(&server.config) else {
+            continue;
+        };
+        match groups
+            .iter_mut()
+            .find(|group| group.fingerprint == fingerprint)
+        {
+            Some(group) => group.ids.push(server.id),
+            None => groups.push(McpDuplicateGroup {
+                fingerprint,
+                ids: vec![server.id],
+            }),
         }
     }
+    groups.retain(|group| group.ids.len() > 1);
+    groups
+}
+
+#[tauri::command]
+fn merge_duplicate_mcp_servers(payload: MergeDuplicateMcpInput) -> Result<Vec<String>, String> {
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let config = configs
+        .iter()
+        .find(|config| config.id == payload.source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())?;
 
-    if !out.contains_key("serverUrl") {
-        if let Some(url) = obj.get("url") {
-            out.insert("serverUrl".to_string(), url.clone());
+    let write_config = mcp_write_config(config, payload.target_path.as_deref())?;
+    let servers = read_mcp_servers(config, &write_config.primary_path)?;
+    let fingerprint_of = |id: &str| {
+        servers
+            .iter()
+            .find(|server| server.id == id)
+            .ok_or_else(|| format!("MCP server {} not found", id))
+            .map(|server| mcp_server_fingerprint(&server.config))
+    };
+    let kept = fingerprint_of(&payload.keep)?
+        .ok_or_else(|| format!("{} has no command or url to compare", payload.keep))?;
+    for id in &payload.remove {
+        if id == &payload.keep {
+            return Err(format!("Cannot remove the kept server {}", id));
+        }
+        if fingerprint_of(id)?.as_deref() != Some(kept.as_str()) {
+            return Err(format!("{} is not a duplicate of {}", id, payload.keep));
         }
     }
 
-    Ok(JsonValue::Object(out))
+    if payload.remove.is_empty() {
+        return Ok(payload.remove);
+    }
+    backup_file(&write_config.primary_path)?;
+    let result = payload
+        .remove
+        .iter()
+        .try_for_each(|id| delete_mcp_server_for_source(&write_config, id));
+    record_history(
+        "merge_duplicate_mcp_servers",
+        &payload.source_id,
+        &payload.remove.join(", "),
+        None,
+        &result,
+    );
+    result.map(|_| payload.remove)
 }
 
-fn read_mcp_servers(config: &McpSourceConfig, path: &Path) -> Result<Vec<McpServer>, String> {
-    let mut servers = Vec::new();
-    if !path.exists() {
-        return Ok(servers);
+#[tauri::command]
+fn diagnose_all(payload: Option<DiagnoseAllInput>) -> Result<DiagnosticReport, String> {
+    let check_urls = payload.is_some_and(|payload| payload.check_urls);
+    let home = resolve_home()?;
+    let settings = read_app_settings(&home);
+    let mut report = DiagnosticReport::default();
+    let issue = |source_id: &str, subject: String, message: String| DiagnosticIssue {
+        source_id: source_id.to_string(),
+        subject,
+        message,
+    };
+
+    if let Err(err) = load_json_value(&settings_path(&home)) {
+        report.config_errors.push(issue(
+            "ananke",
+            settings_path(&home).display().to_string(),
+            err,
+        ));
     }
 
-    match config.kind {
-        McpKind::CodexToml => {
-            let value = load_toml_value(path)?;
-            if let Some(table) = value.get("mcp_servers").and_then(|item| item.as_table()) {
-                for (id, server) in table {
-                    servers.push(McpServer {
-                        id: id.to_string(),
-                        config: toml_to_json(server),
-                    });
+    let agent = ureq::AgentBuilder::new()
+        .user_agent(&user_agent())
+        .timeout(Duration::from_secs(5))
+        .build();
+    for source in source_configs(&home) {
+        if !source_enabled(&settings, source.id) {
+            continue;
+        }
+        if let Ok(entries) = fs::read_dir(&source.root) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.starts_with('.') || !path.is_dir() {
+                    continue;
+                }
+                if find_core_file(&path, &source.core_files).is_none() {
+                    report.missing_core_files.push(issue(
+                        source.id,
+                        name,
+                        format!("No {} found", source.core_files.join(" or ")),
+                    ));
                 }
             }
         }
-        McpKind::ClaudeJson => {
-            let value = load_json_value(path)?;
-            if let Some(servers_value) = value.get("mcpServers") {
-                if let Some(map) = servers_value.as_object() {
-                    for (id, server) in map {
-                        servers.push(McpServer {
-                            id: id.to_string(),
-                            config: server.clone(),
-                        });
-                    }
-                }
+        if !check_urls {
+            continue;
+        }
+        for skill in read_skills(&source) {
+            let Some(url) = skill.source_url.as_deref() else {
+                continue;
+            };
+            if let Err(err) = check_url_reachable(&agent, url) {
+                report
+                    .unreachable_urls
+                    .push(issue(source.id, skill.id.clone(), err));
             }
         }
-        McpKind::AntigravityJson => {
-            let value = load_json_value(path)?;
-            if let Some(servers_value) = value.get("mcpServers") {
-                if let Some(map) = servers_value.as_object() {
-                    for (id, server) in map {
-                        servers.push(McpServer {
-                            id: id.to_string(),
-                            config: antigravity_to_standard_config(server),
-                        });
-                    }
-                }
+    }
+
+    for config in mcp_source_configs(&home) {
+        if !source_enabled(&settings, config.id) {
+            continue;
+        }
+        let mut parse_failed = false;
+        for path in mcp_config_files(&config) {
+            if !path.exists() {
+                continue;
+            }
+            let result = match config.kind {
+                McpKind::CodexToml => load_toml_value(&path).map(|_| ()),
+                _ => load_json_value(&path).map(|_| ()),
+            };
+            if let Err(err) = result {
+                parse_failed = true;
+                report
+                    .config_errors
+                    .push(issue(config.id, path.display().to_string(), err));
             }
         }
-        McpKind::OpenCodeJson => {
-            let value = load_json_value(path)?;
-            if let Some(servers_value) = value.get("mcp") {
-                if let Some(map) = servers_value.as_object() {
-                    for (id, server) in map {
-                        servers.push(McpServer {
-                            id: id.to_string(),
-                            config: opencode_to_standard_config(server),
-                        });
-                    }
-                }
+        if parse_failed {
+            continue;
+        }
+        let Ok(servers) = read_source_mcp_servers(&config) else {
+            continue;
+        };
+        for server in &servers {
+            let Some(command) = server.config.get("command").and_then(|item| item.as_str()) else {
+                continue;
+            };
+            if !command.trim().is_empty() && find_command_on_path(command).is_none() {
+                report.missing_commands.push(issue(
+                    config.id,
+                    server.id.clone(),
+                    format!("Command {} not found on PATH", command.trim()),
+                ));
             }
         }
+        for group in duplicate_mcp_groups(servers) {
+            report.duplicate_mcp_servers.push(issue(
+                config.id,
+                group.ids.join(", "),
+                format!("Same server: {}", group.fingerprint),
+            ));
+        }
     }
 
-    servers.sort_by(|a, b| a.id.cmp(&b.id));
-    Ok(servers)
+    Ok(report)
 }
 
-fn upsert_mcp_servers(
-    config: &McpSourceConfig,
-    servers: HashMap<String, JsonValue>,
-) -> Result<(), String> {
-    match config.kind {
-        McpKind::CodexToml => {
-            let mut value = load_toml_value(&config.primary_path)?;
-            let table = value
-                .as_table_mut()
-                .ok_or_else(|| "Invalid config format".to_string())?;
-            let mcp_entry = table
-                .entry("mcp_servers".to_string())
-                .or_insert_with(|| TomlValue::Table(TomlMap::new()));
-            let mcp_table = mcp_entry
-                .as_table_mut()
-                .ok_or_else(|| "Invalid mcp_servers format".to_string())?;
-
-            for (id, config_value) in servers {
-                let toml_value = json_to_toml(&config_value)?;
-                mcp_table.insert(id, toml_value);
+fn mask_mcp_secrets(config: &mut JsonValue) {
+    for key in ["env", "headers", "http_headers"] {
+        let Some(values) = config.get_mut(key).and_then(|item| item.as_object_mut()) else {
+            continue;
+        };
+        for value in values.values_mut() {
+            if let Some(text) = value.as_str() {
+                if !text.is_empty() && !text.starts_with("${") {
+                    *value = JsonValue::String("***".to_string());
+                }
             }
-
-            save_toml_value(&config.primary_path, &value)?;
         }
-        McpKind::ClaudeJson => {
-            let mut value = load_json_value(&config.primary_path)?;
-            let root = value
-                .as_object_mut()
-                .ok_or_else(|| "Invalid JSON format".to_string())?;
-            let servers_value = root
-                .entry("mcpServers".to_string())
-                .or_insert_with(|| JsonValue::Object(JsonMap::new()));
-            let servers_map = servers_value
-                .as_object_mut()
-                .ok_or_else(|| "Invalid mcpServers format".to_string())?;
+    }
+}
 
-            for (id, config_value) in servers {
-                servers_map.insert(id, config_value);
-            }
+#[tauri::command]
+fn export_mcp_server(payload: ExportMcpServerInput) -> Result<String, String> {
+    let home = resolve_home()?;
+    let configs = mcp_source_configs(&home);
+    let config = configs
+        .iter()
+        .find(|config| config.id == payload.source_id)
+        .ok_or_else(|| "Unknown MCP source".to_string())?;
 
-            save_json_value(&config.primary_path, &value)?;
-        }
-        McpKind::AntigravityJson => {
-            let mut value = load_json_value(&config.primary_path)?;
-            let root = value
-                .as_object_mut()
-                .ok_or_else(|| "Invalid JSON format".to_string())?;
-            let servers_value = root
-                .entry("mcpServers".to_string())
-                .or_insert_with(|| JsonValue::Object(JsonMap::new()));
-            let servers_map = servers_value
-                .as_object_mut()
-                .ok_or_else(|| "Invalid mcpServers format".to_string())?;
+    let mut server = read_source_mcp_servers(config)?
+        .into_iter()
+        .find(|server| server.id == payload.id)
+        .ok_or_else(|| format!("MCP server {} not found", payload.id))?;
+    if !payload.reveal {
+        mask_mcp_secrets(&mut server.config);
+    }
+    let mut servers = JsonMap::new();
+    servers.insert(server.id, server.config);
+    let mut snippet = JsonMap::new();
+    snippet.insert("mcpServers".to_string(), JsonValue::Object(servers));
+    serde_json::to_string_pretty(&JsonValue::Object(snippet))
+        .map_err(|err| format!("Failed to serialize MCP server: {}", err))
+}
+
+fn zip_add_file<W: Write + std::io::Seek>(
+    writer: &mut zip::ZipWriter<W>,
+    name: &str,
+    bytes: &[u8],
+) -> Result<(), String> {
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    writer
+        .start_file(name, options)
+        .map_err(|err| format!("Failed to add {}: {}", name, err))?;
+    writer
+        .write_all(bytes)
+        .map_err(|err| format!("Failed to write {}: {}", name, err))
+}
 
-            for (id, config_value) in servers {
-                let converted = standard_to_antigravity_config(&config_value)?;
-                servers_map.insert(id, converted);
-            }
+#[tauri::command]
+fn migrate_antigravity_location(
+    payload: MigrateAntigravityInput,
+) -> Result<AntigravityMigration, String> {
+    let home = resolve_home()?;
+    let legacy_root = home.join(".antigravity");
+    let new_root = home.join(".gemini").join("antigravity");
+    if !legacy_root.is_dir() {
+        return Err("No legacy Antigravity directory found".to_string());
+    }
 
-            save_json_value(&config.primary_path, &value)?;
-        }
-        McpKind::OpenCodeJson => {
-            let mut value = load_json_value(&config.primary_path)?;
-            let root = value
-                .as_object_mut()
-                .ok_or_else(|| "Invalid JSON format".to_string())?;
-            let mcp_value = root
-                .entry("mcp".to_string())
-                .or_insert_with(|| JsonValue::Object(JsonMap::new()));
-            let mcp_map = mcp_value
-                .as_object_mut()
-                .ok_or_else(|| "Invalid mcp format".to_string())?;
+    let mut migration = AntigravityMigration {
+        skills_copied: Vec::new(),
+        skills_skipped: Vec::new(),
+        servers_added: 0,
+        servers_skipped: 0,
+        legacy_backup_path: None,
+    };
 
-            for (id, config_value) in servers {
-                let converted = standard_to_opencode_config(&config_value)?;
-                mcp_map.insert(id, converted);
+    let legacy_skills = legacy_root.join("skills");
+    let new_skills = new_root.join("skills");
+    if let Ok(entries) = fs::read_dir(&legacy_skills) {
+        for entry in entries.flatten() {
+            if !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            let target = new_skills.join(&name);
+            if target.exists() {
+                migration.skills_skipped.push(name);
+                continue;
             }
+            copy_dir_recursive(&entry.path(), &target)?;
+            migration.skills_copied.push(name);
+        }
+    }
 
-            save_json_value(&config.primary_path, &value)?;
+    let config = mcp_source_configs(&home)
+        .into_iter()
+        .find(|config| matches!(config.kind, McpKind::AntigravityJson))
+        .ok_or_else(|| "Antigravity MCP source missing".to_string())?;
+    let legacy_mcp = legacy_root.join("mcp.json");
+    let new_mcp = new_root.join("mcp_config.json");
+    let existing: HashSet<String> = read_mcp_servers(&config, &new_mcp)?
+        .into_iter()
+        .map(|server| server.id)
+        .collect();
+    let mut to_insert = HashMap::new();
+    for server in read_mcp_servers(&config, &legacy_mcp)? {
+        if existing.contains(&server.id) {
+            migration.servers_skipped += 1;
+        } else {
+            to_insert.insert(server.id, server.config);
+            migration.servers_added += 1;
         }
     }
+    if !to_insert.is_empty() {
+        let mut write_config = config.clone();
+        write_config.primary_path = new_mcp;
+        upsert_mcp_servers(&write_config, to_insert)?;
+    }
 
-    Ok(())
+    if payload.remove_legacy {
+        let backup = legacy_root.with_file_name(format!(".antigravity.{}.bak", unix_timestamp()));
+        fs::rename(&legacy_root, &backup)
+            .map_err(|err| format!("Failed to move {}: {}", legacy_root.display(), err))?;
+        migration.legacy_backup_path = Some(backup.display().to_string());
+    }
+
+    Ok(migration)
 }
 
-fn delete_mcp_server_for_source(config: &McpSourceConfig, server_id: &str) -> Result<(), String> {
-    match config.kind {
-        McpKind::CodexToml => {
-            let mut value = load_toml_value(&config.primary_path)?;
-            let table = value
-                .as_table_mut()
-                .ok_or_else(|| "Invalid config format".to_string())?;
-            if let Some(mcp_table) = table
-                .get_mut("mcp_servers")
-                .and_then(|item| item.as_table_mut())
-            {
-                if mcp_table.remove(server_id).is_none() {
-                    return Err("MCP server not found".to_string());
-                }
+#[tauri::command]
+fn export_all(payload: ExportAllInput) -> Result<ExportSummary, String> {
+    let dest = PathBuf::from(payload.dest_path.trim());
+    if dest.as_os_str().is_empty() {
+        return Err("Destination path is required".to_string());
+    }
+    let home = resolve_home()?;
+    let file = fs::File::create(&dest)
+        .map_err(|err| format!("Failed to create {}: {}", dest.display(), err))?;
+    let mut writer = zip::ZipWriter::new(file);
+    let mut manifest = ExportManifest {
+        format_version: EXPORT_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: unix_timestamp(),
+        skill_sources: Vec::new(),
+        mcp_sources: Vec::new(),
+    };
+    let mut skill_count = 0;
+    let mut server_count = 0;
+
+    for source in source_configs(&home) {
+        let mut items = Vec::new();
+        for skill in read_skills(&source) {
+            let skill_path = resolve_skill_dir(&source, &skill.id, "export")?;
+            let prefix = format!("skills/{}/{}", source.id, skill.id);
+            if skill_path.is_file() {
+                let bytes = fs::read(&skill_path)
+                    .map_err(|err| format!("Failed to read {}: {}", skill_path.display(), err))?;
+                zip_add_file(&mut writer, &prefix, &bytes)?;
             } else {
-                return Err("No MCP servers configured".to_string());
+                let mut files = Vec::new();
+                collect_skill_files(&skill_path, "", &mut files)?;
+                let ignore = SkillIgnore::load(&skill_path);
+                files.retain(|(relative, _)| !ignore.is_ignored(relative));
+                let record = skill_path.join(SKILL_SOURCE_FILENAME);
+                if record.is_file() {
+                    files.push((SKILL_SOURCE_FILENAME.to_string(), record));
+                }
+                for (relative, path) in files {
+                    let bytes = fs::read(&path)
+                        .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+                    zip_add_file(&mut writer, &format!("{}/{}", prefix, relative), &bytes)?;
+                }
             }
-            save_toml_value(&config.primary_path, &value)?;
+            items.push(skill.id);
         }
-        McpKind::ClaudeJson | McpKind::AntigravityJson => {
-            let mut value = load_json_value(&config.primary_path)?;
-            let root = value
-                .as_object_mut()
-                .ok_or_else(|| "Invalid JSON format".to_string())?;
-            let servers_value = root
-                .get_mut("mcpServers")
-                .and_then(|item| item.as_object_mut())
-                .ok_or_else(|| "No mcpServers configured".to_string())?;
+        if !items.is_empty() {
+            skill_count += items.len();
+            manifest.skill_sources.push(ExportedSource {
+                id: source.id.to_string(),
+                items,
+            });
+        }
+    }
 
-            if servers_value.remove(server_id).is_none() {
-                return Err("MCP server not found".to_string());
-            }
-            save_json_value(&config.primary_path, &value)?;
+    for config in mcp_source_configs(&home) {
+        let servers = read_source_mcp_servers(&config)?;
+        if servers.is_empty() {
+            continue;
         }
-        McpKind::OpenCodeJson => {
-            let mut value = load_json_value(&config.primary_path)?;
-            let root = value
-                .as_object_mut()
-                .ok_or_else(|| "Invalid JSON format".to_string())?;
-            let mcp_value = root
-                .get_mut("mcp")
-                .and_then(|item| item.as_object_mut())
-                .ok_or_else(|| "No mcp configured".to_string())?;
+        let inputs = read_mcp_inputs(&config, &resolve_read_path(&config))?;
+        let mut servers_map = JsonMap::new();
+        let mut items = Vec::new();
+        for server in servers {
+            items.push(server.id.clone());
+            servers_map.insert(server.id, server.config);
+        }
+        let mut snapshot = JsonMap::new();
+        snapshot.insert("mcpServers".to_string(), JsonValue::Object(servers_map));
+        if !inputs.is_empty() {
+            snapshot.insert("inputs".to_string(), JsonValue::Array(inputs));
+        }
+        let bytes = serde_json::to_vec_pretty(&JsonValue::Object(snapshot))
+            .map_err(|err| format!("Failed to serialize MCP snapshot: {}", err))?;
+        zip_add_file(&mut writer, &format!("mcp/{}.json", config.id), &bytes)?;
+        server_count += items.len();
+        manifest.mcp_sources.push(ExportedSource {
+            id: config.id.to_string(),
+            items,
+        });
+    }
 
-            if mcp_value.remove(server_id).is_none() {
-                return Err("MCP server not found".to_string());
-            }
-            save_json_value(&config.primary_path, &value)?;
+    let bytes = serde_json::to_vec_pretty(&manifest)
+        .map_err(|err| format!("Failed to serialize manifest: {}", err))?;
+    zip_add_file(&mut writer, EXPORT_MANIFEST_NAME, &bytes)?;
+    writer
+        .finish()
+        .map_err(|err| format!("Failed to finish archive: {}", err))?;
+
+    Ok(ExportSummary {
+        path: dest.display().to_string(),
+        skills: skill_count,
+        mcp_servers: server_count,
+    })
+}
+
+fn safe_relative_path(relative: &str) -> Option<PathBuf> {
+    let path = Path::new(relative);
+    let mut components = path.components().peekable();
+    components.peek()?;
+    components
+        .all(|component| matches!(component, std::path::Component::Normal(_)))
+        .then(|| path.to_path_buf())
+}
+
+fn read_archive_entry<R: Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+) -> Result<Vec<u8>, String> {
+    let entry = archive
+        .by_name(name)
+        .map_err(|err| format!("Failed to read {}: {}", name, err))?;
+    read_capped(entry, name, MAX_ARCHIVE_ENTRY_BYTES)
+}
+
+fn import_skill_from_archive<R: Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    names: &[String],
+    source: &SourceConfig,
+    skill_id: &str,
+    overwrite: bool,
+) -> Result<&'static str, String> {
+    let skill_rel = safe_relative_path(skill_id)
+        .filter(|path| path.components().count() == 1)
+        .ok_or_else(|| "Invalid skill id".to_string())?;
+    let entry_name = format!("skills/{}/{}", source.id, skill_id);
+    let prefix = format!("{}/", entry_name);
+    let mut files = Vec::new();
+    for name in names {
+        if name == &entry_name {
+            files.push((name.clone(), PathBuf::new()));
+        } else if let Some(relative) = name.strip_prefix(&prefix) {
+            let relative = safe_relative_path(relative)
+                .ok_or_else(|| format!("Archive contains unsafe path {}", name))?;
+            files.push((name.clone(), relative));
         }
     }
+    if files.is_empty() {
+        return Err("Skill files missing from archive".to_string());
+    }
 
-    Ok(())
+    let target = source.root.join(&skill_rel);
+    let existed = target.exists();
+    if existed {
+        if !overwrite {
+            return Ok("skipped");
+        }
+        remove_skill(source, skill_id)?;
+    }
+    fs::create_dir_all(&source.root)
+        .map_err(|err| format!("Failed to create {}: {}", source.root.display(), err))?;
+    let mut extracted = 0u64;
+    for (name, relative) in files {
+        let bytes = read_archive_entry(archive, &name)?;
+        extracted += bytes.len() as u64;
+        if extracted > MAX_ARCHIVE_EXTRACTED_BYTES {
+            return Err(format!(
+                "Archive expands beyond {} bytes",
+                MAX_ARCHIVE_EXTRACTED_BYTES
+            ));
+        }
+        write_bytes_to_path(&bytes, &target.join(relative))?;
+    }
+    Ok(if existed { "overwritten" } else { "imported" })
 }
 
 #[tauri::command]
-fn list_skills() -> Result<Vec<SkillSource>, String> {
+fn import_all(payload: ImportAllInput) -> Result<Vec<ImportItemReport>, String> {
+    let overwrite = match payload.on_conflict.as_deref().unwrap_or("skip") {
+        "skip" => false,
+        "overwrite" => true,
+        other => return Err(format!("Unknown onConflict mode: {}", other)),
+    };
+    let archive_path = PathBuf::from(payload.archive_path.trim());
+    let file = fs::File::open(&archive_path)
+        .map_err(|err| format!("Failed to open {}: {}", archive_path.display(), err))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|err| format!("Invalid archive: {}", err))?;
+
+    let mut names = Vec::new();
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index(index)
+            .map_err(|err| format!("Invalid archive entry: {}", err))?;
+        if entry.enclosed_name().is_none() {
+            return Err(format!("Archive contains unsafe path {}", entry.name()));
+        }
+        if !entry.is_dir() {
+            names.push(entry.name().to_string());
+        }
+    }
+
+    let manifest: ExportManifest =
+        serde_json::from_slice(&read_archive_entry(&mut archive, EXPORT_MANIFEST_NAME)?)
+            .map_err(|err| format!("Invalid manifest: {}", err))?;
+    if manifest.format_version > EXPORT_FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported export format version {}",
+            manifest.format_version
+        ));
+    }
+
     let home = resolve_home()?;
     let sources = source_configs(&home);
-    let mut response = Vec::new();
+    let configs = mcp_source_configs(&home);
+    let mut reports = Vec::new();
+    let report = |kind: &str, source_id: &str, item_id: &str, result: Result<&str, String>| {
+        let (status, error) = match result {
+            Ok(status) => (status.to_string(), None),
+            Err(err) => ("failed".to_string(), Some(err)),
+        };
+        ImportItemReport {
+            kind: kind.to_string(),
+            source_id: source_id.to_string(),
+            item_id: item_id.to_string(),
+            status,
+            error,
+        }
+    };
 
-    for source in sources {
-        let installed = source.install_root.is_dir();
-        if !installed {
-            continue;
+    for exported in &manifest.skill_sources {
+        let source = sources.iter().find(|source| source.id == exported.id);
+        for skill_id in &exported.items {
+            let result = match source {
+                Some(source) => {
+                    import_skill_from_archive(&mut archive, &names, source, skill_id, overwrite)
+                }
+                None => Err("Unknown skill source".to_string()),
+            };
+            reports.push(report("skill", &exported.id, skill_id, result));
         }
-        let root_exists = source.root.is_dir();
-        let skills = if root_exists {
-            read_skills(&source)
+    }
+
+    for exported in &manifest.mcp_sources {
+        let snapshot = configs
+            .iter()
+            .find(|config| config.id == exported.id)
+            .ok_or_else(|| "Unknown MCP source".to_string())
+            .and_then(|config| {
+                let bytes = read_archive_entry(&mut archive, &format!("mcp/{}.json", config.id))?;
+                let json = String::from_utf8(bytes)
+                    .map_err(|err| format!("MCP snapshot is not UTF-8: {}", err))?;
+                let servers = parse_mcp_json(&json)?;
+                let inputs = parse_mcp_inputs(&json)?;
+                let existing: HashSet<String> = read_source_mcp_servers(config)?
+                    .into_iter()
+                    .map(|server| server.id)
+                    .collect();
+                Ok((config, servers, inputs, existing))
+            });
+        let (config, servers, inputs, existing) = match snapshot {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                for server_id in &exported.items {
+                    reports.push(report("mcp", &exported.id, server_id, Err(err.clone())));
+                }
+                continue;
+            }
+        };
+
+        let mut to_insert = HashMap::new();
+        let mut statuses = Vec::new();
+        for server_id in &exported.items {
+            let status = match servers.get(server_id) {
+                None => Err("Server missing from archive snapshot".to_string()),
+                Some(_) if existing.contains(server_id) && !overwrite => Ok("skipped"),
+                Some(server) => {
+                    to_insert.insert(server_id.clone(), server.clone());
+                    Ok(if existing.contains(server_id) {
+                        "overwritten"
+                    } else {
+                        "imported"
+                    })
+                }
+            };
+            statuses.push((server_id, status));
+        }
+        let written = if to_insert.is_empty() {
+            Ok(())
         } else {
-            vec![]
+            let mut ids: Vec<String> = to_insert.keys().cloned().collect();
+            ids.sort();
+            let written = upsert_mcp_servers(config, to_insert)
+                .and_then(|_| merge_mcp_inputs(config, inputs));
+            record_history(
+                "import_mcp_servers",
+                &exported.id,
+                &ids.join(", "),
+                None,
+                &written,
+            );
+            written
         };
-        response.push(SkillSource {
-            id: source.id.to_string(),
-            label: source.label.to_string(),
-            root: source.root.display().to_string(),
-            exists: root_exists,
-            skills,
-        });
+        for (server_id, status) in statuses {
+            let status = match (&written, status) {
+                (Err(err), Ok(status)) if status != "skipped" => Err(err.clone()),
+                (_, status) => status,
+            };
+            reports.push(report("mcp", &exported.id, server_id, status));
+        }
     }
 
-    Ok(response)
+    Ok(reports)
 }
 
 #[tauri::command]
-fn list_skill_tree(payload: SkillTreeInput) -> Result<SkillTreeNode, String> {
+fn get_environment_info() -> Result<EnvironmentInfo, String> {
     let home = resolve_home()?;
-    let sources = source_configs(&home);
-    let source = sources
+    let skill_sources = source_configs(&home)
         .iter()
-        .find(|source| source.id == payload.source_id)
-        .ok_or_else(|| "Unknown skill source".to_string())?;
+        .map(|source| SkillSourcePaths {
+            id: source.id.to_string(),
+            install_root: source.install_root.display().to_string(),
+            install_root_exists: source.install_root.is_dir(),
+            root: source.root.display().to_string(),
+            root_exists: source.root.is_dir(),
+        })
+        .collect();
+    let mcp_sources = mcp_source_configs(&home)
+        .iter()
+        .map(|config| {
+            let read_path = resolve_read_path(config);
+            McpSourcePaths {
+                id: config.id.to_string(),
+                primary_path: config.primary_path.display().to_string(),
+                read_path_exists: read_path.exists(),
+                read_path: read_path.display().to_string(),
+                read_paths: config
+                    .read_paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect(),
+            }
+        })
+        .collect();
 
-    let skill_dir = source.root.join(&payload.skill_id);
-    if !skill_dir.exists() {
-        return Err("Skill not found".to_string());
-    }
+    Ok(EnvironmentInfo {
+        home: home.display().to_string(),
+        skill_sources,
+        mcp_sources,
+        token_source: github_token_env_var().map(str::to_string),
+        offline: offline_mode(),
+        log_path: log_file_path(&home).display().to_string(),
+    })
+}
 
-    let root_canon =
-        fs::canonicalize(&source.root).map_err(|err| format!("Failed to resolve root: {}", err))?;
-    let skill_canon =
-        fs::canonicalize(&skill_dir).map_err(|err| format!("Failed to resolve skill: {}", err))?;
-    if !skill_canon.starts_with(&root_canon) {
-        return Err("Refusing to read outside agent root".to_string());
+#[tauri::command]
+fn get_history(payload: Option<HistoryInput>) -> Result<Vec<HistoryEntry>, String> {
+    let home = resolve_home()?;
+    let path = history_path(&home);
+    if !path.exists() {
+        return Ok(Vec::new());
     }
-
-    build_skill_tree(&skill_dir)
+    let content = fs::read_to_string(&path)
+        .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+    let limit = payload
+        .and_then(|payload| payload.limit)
+        .unwrap_or(usize::MAX);
+    Ok(content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect())
 }
 
 #[tauri::command]
-fn install_skill_from_url(payload: InstallSkillInput) -> Result<SkillItem, String> {
-    if let Some(token) = &payload.token {
-        TOKEN_OVERRIDE.with(|cell| *cell.borrow_mut() = Some(token.clone()));
-    }
-    struct TokenGuard;
-    impl Drop for TokenGuard {
-        fn drop(&mut self) {
-            TOKEN_OVERRIDE.with(|cell| *cell.borrow_mut() = None);
-        }
-    }
-    let _guard = TokenGuard;
+fn get_log_path() -> Result<String, String> {
     let home = resolve_home()?;
-    let sources = source_configs(&home);
-    let source = sources
-        .iter()
-        .find(|source| source.id == payload.source_id)
-        .ok_or_else(|| "Unknown skill source".to_string())?;
+    Ok(log_file_path(&home).display().to_string())
+}
 
-    fs::create_dir_all(&source.root)
-        .map_err(|err| format!("Failed to create {}: {}", source.root.display(), err))?;
+#[tauri::command]
+fn get_offline_mode() -> bool {
+    offline_mode()
+}
 
-    let github_location = parse_github_location(&payload.url).ok();
-    let github_agent = github_location
-        .as_ref()
-        .map(|_| ureq::AgentBuilder::new().user_agent("Ananke/0.1").build());
-    let github_branches = match (github_location.as_ref(), github_agent.as_ref()) {
-        (Some(location), Some(agent)) => Some(github_branch_candidates(agent, location)),
-        _ => None,
-    };
-    let mut content = None;
-    let mut core_file_name = None;
-    let mut last_error = None;
-    let mut selected_branch = None;
+#[tauri::command]
+fn set_offline_mode(payload: OfflineModeInput) -> bool {
+    OFFLINE_MODE.store(payload.enabled, Ordering::Relaxed);
+    offline_mode()
+}
 
-    'core_lookup: for file_name in &source.core_files {
-        if let (Some(location), Some(agent), Some(branches)) = (
-            github_location.as_ref(),
-            github_agent.as_ref(),
-            github_branches.as_ref(),
-        ) {
-            for branch in branches {
-                let path = github_file_path(location, file_name);
-                match fetch_github_file_content(
-                    agent,
-                    &location.owner,
-                    &location.repo,
-                    &path,
-                    branch,
-                ) {
-                    Ok(bytes) => match String::from_utf8(bytes) {
-                        Ok(body) => {
-                            content = Some(body);
-                            core_file_name = Some(file_name.to_string());
-                            selected_branch = Some(branch.to_string());
-                            break 'core_lookup;
-                        }
-                        Err(err) => {
-                            last_error = Some(format!("GitHub file is not UTF-8: {}", err));
-                        }
-                    },
-                    Err(err) => {
-                        last_error = Some(err);
-                    }
-                }
-            }
-        } else {
-            let candidates = parse_skill_urls(&payload.url, file_name)?;
-            match fetch_skill_content(candidates) {
-                Ok(body) => {
-                    content = Some(body);
-                    core_file_name = Some(file_name.to_string());
-                    break;
-                }
-                Err(err) => {
-                    last_error = Some(err);
-                }
+#[tauri::command]
+fn set_github_token(payload: GithubTokenInput) -> Result<bool, String> {
+    let token = payload
+        .token
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty());
+    let has_token = token.is_some();
+    *SESSION_GITHUB_TOKEN
+        .lock()
+        .map_err(|_| "GitHub token lock poisoned".to_string())? = token;
+    Ok(has_token)
+}
+
+#[tauri::command]
+fn clear_github_token() -> Result<(), String> {
+    *SESSION_GITHUB_TOKEN
+        .lock()
+        .map_err(|_| "GitHub token lock poisoned".to_string())? = None;
+    Ok(())
+}
+
+#[tauri::command]
+fn get_enabled_sources() -> Result<Option<Vec<String>>, String> {
+    let home = resolve_home()?;
+    Ok(read_app_settings(&home).enabled_sources)
+}
+
+#[tauri::command]
+fn set_enabled_sources(payload: EnabledSourcesInput) -> Result<Option<Vec<String>>, String> {
+    let home = resolve_home()?;
+    let mut settings = read_app_settings(&home);
+    settings.enabled_sources = payload.source_ids.map(|ids| {
+        let mut enabled: Vec<String> = Vec::new();
+        for id in ids {
+            let id = id.trim().to_string();
+            if !id.is_empty() && !enabled.contains(&id) {
+                enabled.push(id);
             }
         }
+        enabled
+    });
+    write_app_settings(&home, &settings)?;
+    Ok(settings.enabled_sources)
+}
+
+#[tauri::command]
+fn set_home_override(payload: HomeOverrideInput) -> Result<String, String> {
+    let path = payload
+        .path
+        .as_deref()
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from);
+    if let Some(path) = &path {
+        if !path.is_absolute() {
+            return Err("Home override must be an absolute path".to_string());
+        }
+        if !path.is_dir() {
+            return Err(format!(
+                "Home override {} is not a directory",
+                path.display()
+            ));
+        }
     }
+    *HOME_OVERRIDE
+        .lock()
+        .map_err(|_| "Home override lock poisoned".to_string())? = path;
+    resolve_home().map(|home| home.display().to_string())
+}
 
-    let content = content
-        .ok_or_else(|| last_error.unwrap_or_else(|| "Unable to download skill file".to_string()))?;
-    let core_file_name = core_file_name.ok_or_else(|| "Missing core file".to_string())?;
-    let is_markdown = core_file_name.ends_with(".md");
-    let (metadata, _) = if is_markdown {
-        parse_frontmatter(&content)
-    } else {
-        (HashMap::new(), content.clone())
-    };
+fn command_handler() -> impl Fn(tauri::ipc::Invoke<tauri::Wry>) -> bool + Send + Sync + 'static {
+    tauri::generate_handler![
+        list_skills,
+        list_skill_tree,
+        skill_hash,
+        find_skill_everywhere,
+        relink_skills,
+        canonical_skill_url,
+        get_skill_icon,
+        normalize_skill_slug,
+        reslug_all_skills,
+        diff_skill_trees,
+        preview_remote_skill,
+        preview_install_slug,
+        list_remote_skills,
+        install_skill_from_url,
+        install_skill_to_sources,
+        sync_skill_from_url,
+        sync_all_skills,
+        sync_skill_core_only,
+        reset_skill_to_upstream,
+        diff_skill_against_upstream,
+        diff_skill_against_url,
+        set_skill_source_url,
+        set_skill_metadata,
+        bulk_set_metadata,
+        prune_orphaned_skill_source_files,
+        list_github_branches,
+        github_rate_limit,
+        get_skill_description,
+        get_skill_commit,
+        delete_skill,
+        delete_skills,
+        sync_skills_from_agent,
+        list_mcp_sources,
+        list_mcp_servers_by_type,
+        list_mcp_servers_from_path,
+        upsert_mcp_servers_to_path,
+        get_source_summary,
+        sync_skills_to_all_sources,
+        init_skills_dir,
+        sync_mcp_from_agent,
+        migrate_mcp_servers,
+        merge_mcp_server,
+        set_all_mcp_servers_enabled,
+        check_mcp_config_health,
+        test_mcp_server,
+        check_mcp_commands,
+        get_mcp_read_paths,
+        get_mcp_schema,
+        normalize_mcp_config,
+        upsert_mcp_server_json,
+        validate_mcp_json,
+        preview_mcp_upsert,
+        delete_mcp_server,
+        export_mcp_server,
+        find_duplicate_mcp_servers,
+        snapshot_mcp_config,
+        restore_mcp_snapshot,
+        merge_duplicate_mcp_servers,
+        diagnose_all,
+        get_offline_mode,
+        set_offline_mode,
+        set_home_override,
+        set_github_token,
+        clear_github_token,
+        get_enabled_sources,
+        set_enabled_sources,
+        migrate_antigravity_location,
+        export_all,
+        import_all,
+        get_history,
+        get_log_path,
+        get_environment_info
+    ]
+}
 
-    let name = metadata
-        .get("name")
-        .cloned()
-        .filter(|value| !value.trim().is_empty())
-        .unwrap_or_else(|| fallback_name_from_url(&payload.url, &core_file_name));
-
-    let base_slug = slugify(&name);
-    let mut skill_dir = source.root.join(&base_slug);
-    if skill_dir.exists() {
-        let mut suffix = 1;
-        loop {
-            let candidate = source.root.join(format!("{}-{}", base_slug, suffix));
-            if !candidate.exists() {
-                skill_dir = candidate;
-                break;
-            }
-            suffix += 1;
-        }
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    init_logging();
+    let handler = command_handler();
+    tauri::Builder::default()
+        .plugin(tauri_plugin_opener::init())
+        .invoke_handler(move |invoke| {
+            let command = invoke.message.command().to_string();
+            let started = Instant::now();
+            log::debug!("command {} started", command);
+            let handled = handler(invoke);
+            log::info!(
+                "command {} finished in {} ms",
+                command,
+                started.elapsed().as_millis()
+            );
+            handled
+        })
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "ananke-test-{}-{}-{}",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
     }
 
-    fs::create_dir_all(&skill_dir)
-        .map_err(|err| format!("Failed to create {}: {}", skill_dir.display(), err))?;
-    let core_path = skill_dir.join(&core_file_name);
-    if let (Some(location), Some(agent), Some(mut branches)) =
-        (github_location, github_agent, github_branches)
-    {
-        if let Some(branch) = selected_branch {
-            branches.retain(|item| item != &branch);
-            branches.insert(0, branch);
-        }
-        let mut last_download_error = None;
-        let mut downloaded = false;
-        for branch in branches {
-            match download_github_directory(&agent, &location, &branch, &skill_dir) {
-                Ok(_) => {
-                    downloaded = true;
-                    break;
-                }
-                Err(err) => {
-                    last_download_error = Some(err);
-                }
+    fn zip_bytes(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        {
+            let mut writer = zip::ZipWriter::new(&mut buffer);
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            for (name, bytes) in files {
+                writer.start_file(*name, options).unwrap();
+                writer.write_all(bytes).unwrap();
             }
+            writer.finish().unwrap();
         }
-        if !downloaded {
-            return Err(last_download_error
-                .unwrap_or_else(|| "Unable to download GitHub directory".to_string()));
+        buffer.into_inner()
+    }
+
+    fn test_source(root: &Path, flat: bool) -> SourceConfig {
+        SourceConfig {
+            id: "test",
+            label: "Test",
+            install_root: root.to_path_buf(),
+            root: root.to_path_buf(),
+            core_files: vec!["SKILL.md"],
+            flat,
         }
     }
 
-    write_skill_source_url(&skill_dir, &payload.url)?;
-    fs::write(&core_path, content)
-        .map_err(|err| format!("Failed to write {}: {}", core_file_name, err))?;
+    fn write_skill(dir: &Path, name: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(
+            dir.join("SKILL.md"),
+            format!("---\nname: {}\n---\n# {}\n", name, name),
+        )
+        .unwrap();
+    }
 
-    load_skill(&skill_dir, &core_path, &core_file_name, source)
-}
+    #[test]
+    fn file_style_github_url_resolves_to_skill_directory() {
+        let blob =
+            parse_github_location("https://github.com/acme/skills/blob/main/tools/pdf/SKILL.md")
+                .unwrap();
+        assert_eq!(github_skill_dir_path(&blob, "SKILL.md"), "tools/pdf");
 
-#[tauri::command]
-fn sync_skill_from_url(payload: SyncSkillInput) -> Result<SkillItem, String> {
-    if let Some(token) = &payload.token {
-        TOKEN_OVERRIDE.with(|cell| *cell.borrow_mut() = Some(token.clone()));
+        let raw = parse_github_location(
+            "https://raw.githubusercontent.com/acme/skills/main/tools/pdf/SKILL.md",
+        )
+        .unwrap();
+        assert_eq!(github_skill_dir_path(&raw, "SKILL.md"), "tools/pdf");
+
+        let tree =
+            parse_github_location("https://github.com/acme/skills/tree/main/tools/pdf").unwrap();
+        assert_eq!(github_skill_dir_path(&tree, "SKILL.md"), "tools/pdf");
     }
-    struct TokenGuard;
-    impl Drop for TokenGuard {
-        fn drop(&mut self) {
-            TOKEN_OVERRIDE.with(|cell| *cell.borrow_mut() = None);
-        }
+
+    #[test]
+    fn html_bodies_are_detected() {
+        assert!(looks_like_html("<!DOCTYPE html><html></html>"));
+        assert!(looks_like_html("\u{feff}  <html lang=\"en\">"));
+        assert!(looks_like_html(
+            "<!-- banner -->\n<head><title>x</title></head>"
+        ));
+        assert!(!looks_like_html("---\nname: pdf\n---\n# PDF"));
+        assert!(!looks_like_html("<details>inline markdown html</details>"));
     }
-    let _guard = TokenGuard;
 
-    let home = resolve_home()?;
-    let sources = source_configs(&home);
-    let source = sources
-        .iter()
-        .find(|source| source.id == payload.source_id)
-        .ok_or_else(|| "Unknown skill source".to_string())?;
+    #[test]
+    fn zip_content_types_are_recognized() {
+        assert!(is_zip_content_type("application/zip"));
+        assert!(is_zip_content_type("Application/X-Zip-Compressed"));
+        assert!(!is_zip_content_type("text/markdown"));
+        assert!(!is_zip_content_type("application/octet-stream"));
+    }
 
-    let skill_dir = source.root.join(&payload.skill_id);
-    if !skill_dir.exists() {
-        return Err("Skill not found".to_string());
+    #[test]
+    fn archive_entries_are_capped_when_decompressed() {
+        let bytes = zip_bytes(&[("big.txt", &vec![b'a'; 64 * 1024])]);
+        assert!(bytes.len() < 4 * 1024);
+
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+        let entry = archive.by_name("big.txt").unwrap();
+        assert!(read_capped(entry, "big.txt", 1024).is_err());
+        let entry = archive.by_name("big.txt").unwrap();
+        assert_eq!(
+            read_capped(entry, "big.txt", 64 * 1024).unwrap().len(),
+            64 * 1024
+        );
     }
 
-    let root_canon =
-        fs::canonicalize(&source.root).map_err(|err| format!("Failed to resolve root: {}", err))?;
-    let skill_canon =
-        fs::canonicalize(&skill_dir).map_err(|err| format!("Failed to resolve skill: {}", err))?;
-    if !skill_canon.starts_with(&root_canon) {
-        return Err("Refusing to sync outside agent root".to_string());
+    #[test]
+    fn env_file_values_are_listed_masked_and_not_saved() {
+        let dir = scratch_dir("env-file");
+        fs::write(dir.join(".env"), "API_KEY=secret\nexport REGION=\"eu\"\n").unwrap();
+        let mut servers = vec![McpServer {
+            id: "api".to_string(),
+            config: serde_json::json!({
+                "command": "api-server",
+                "envFile": ".env",
+                "env": { "REGION": "us" }
+            }),
+        }];
+        let mut warnings = Vec::new();
+        let keys = env_file_keys(&mut servers, &dir, &mut warnings);
+        assert!(warnings.is_empty());
+        assert_eq!(keys["api"], vec!["API_KEY", "REGION"]);
+        let config = &servers[0].config;
+        assert_eq!(config["env"]["API_KEY"], "***");
+        assert_eq!(config["env"]["REGION"], "us");
+        assert_eq!(config["envFile"], ".env");
+
+        let mut saved = servers.remove(0).config;
+        strip_env_file_values(&mut saved, &dir);
+        assert_eq!(saved["envFile"], ".env");
+        assert_eq!(saved["env"], serde_json::json!({ "REGION": "us" }));
+        fs::remove_dir_all(&dir).unwrap();
     }
 
-    let (core_file_path, core_file_name) = find_core_file(&skill_dir, &source.core_files)
-        .ok_or_else(|| "Missing core file".to_string())?;
+    #[test]
+    fn mcp_test_reports_and_substitutes_inputs() {
+        let config = serde_json::json!({
+            "command": "api-server",
+            "env": { "API_KEY": "${input:api-key}", "REGION": "${input:region}" }
+        });
+        let inputs = vec![serde_json::json!({
+            "id": "api-key",
+            "type": "promptString",
+            "password": true
+        })];
+        let missing = missing_input_definitions(&config, &inputs, &HashMap::new());
+        assert_eq!(missing.len(), 2);
+        assert_eq!(missing[0]["password"], true);
+        assert_eq!(missing[1]["id"], "region");
+
+        let provided: HashMap<String, String> = [
+            ("api-key".to_string(), "secret".to_string()),
+            ("region".to_string(), "eu".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        assert!(missing_input_definitions(&config, &inputs, &provided).is_empty());
+        let mut resolved = config.clone();
+        substitute_input_values(&mut resolved, &provided);
+        assert_eq!(resolved["env"]["API_KEY"], "secret");
+        assert_eq!(resolved["env"]["REGION"], "eu");
+    }
 
-    if let Ok(location) = parse_github_location(&payload.url) {
-        let agent = ureq::AgentBuilder::new().user_agent("Ananke/0.1").build();
-        let mut branches = github_branch_candidates(&agent, &location);
-        let mut last_download_error = None;
-        let mut confirmed_branch = None;
-        for branch in &branches {
-            match download_github_directory(&agent, &location, branch, &skill_dir) {
-                Ok(_) => {
-                    confirmed_branch = Some(branch.to_string());
-                    break;
-                }
-                Err(err) => {
-                    last_download_error = Some(err);
-                }
-            }
-        }
-        if confirmed_branch.is_none() {
-            return Err(last_download_error
-                .unwrap_or_else(|| "Unable to download GitHub directory".to_string()));
-        }
-        let branch = confirmed_branch.unwrap_or_else(|| branches.remove(0));
-        let path = github_file_path(&location, &core_file_name);
-        let content =
-            fetch_github_file_content(&agent, &location.owner, &location.repo, &path, &branch)
-                .and_then(|bytes| {
-                    String::from_utf8(bytes)
-                        .map_err(|err| format!("GitHub file is not UTF-8: {}", err))
-                })?;
-        write_skill_source_url(&skill_dir, &payload.url)?;
-        fs::write(&core_file_path, content)
-            .map_err(|err| format!("Failed to write {}: {}", core_file_name, err))?;
-    } else {
-        let candidates = parse_skill_urls(&payload.url, &core_file_name)?;
-        let content = fetch_skill_content(candidates)?;
-        write_skill_source_url(&skill_dir, &payload.url)?;
-        fs::write(&core_file_path, content)
-            .map_err(|err| format!("Failed to write {}: {}", core_file_name, err))?;
+    #[test]
+    fn initialize_responses_are_parsed_from_json_and_sse() {
+        let json = r#"{"jsonrpc":"2.0","id":1,"result":{"serverInfo":{"name":"demo"}}}"#;
+        assert_eq!(
+            parse_initialize_body(json).unwrap().unwrap()["name"],
+            "demo"
+        );
+        let sse = format!("event: message\ndata: {}\n\n", json);
+        assert_eq!(
+            parse_initialize_body(&sse).unwrap().unwrap()["name"],
+            "demo"
+        );
+        let error = r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32600,"message":"bad"}}"#;
+        assert!(parse_initialize_body(error).is_err());
     }
 
-    load_skill(&skill_dir, &core_file_path, &core_file_name, source)
-}
+    #[test]
+    fn flat_skills_resolve_to_their_file() {
+        let root = scratch_dir("flat-skills");
+        let source = test_source(&root, true);
+        write_skill(&root.join("pdf"), "pdf");
+        fs::write(
+            root.join("review.md"),
+            "---\nname: review\n---\nReview code\n",
+        )
+        .unwrap();
 
-#[tauri::command]
-fn delete_skill(payload: DeleteSkillInput) -> Result<(), String> {
-    let home = resolve_home()?;
-    let sources = source_configs(&home);
-    let source = sources
-        .iter()
-        .find(|source| source.id == payload.source_id)
-        .ok_or_else(|| "Unknown skill source".to_string())?;
+        let ids: Vec<String> = read_skills(&source)
+            .into_iter()
+            .map(|skill| skill.id)
+            .collect();
+        assert_eq!(ids, vec!["pdf", "review.md"]);
+        for id in &ids {
+            let skill_dir = root.join(id);
+            let (core_path, core_name) = skill_core_file(&skill_dir, &source).unwrap();
+            assert!(load_skill(&skill_dir, &core_path, &core_name, &source).is_ok());
+            assert!(compute_skill_hash(&skill_dir).is_ok());
+        }
 
-    let skill_dir = source.root.join(&payload.skill_id);
-    if !skill_dir.exists() {
-        return Err("Skill not found".to_string());
+        let flat = root.join("review.md");
+        assert_eq!(skill_core_file(&flat, &source).unwrap().1, "review.md");
+        assert!(skill_core_file(&flat, &test_source(&root, false)).is_err());
+        let err = sync_skill_dir(&source, &flat, "https://example.com/review.md", None, false)
+            .err()
+            .unwrap();
+        assert_eq!(err, "Flat skill files cannot be synced");
+        fs::remove_dir_all(&root).unwrap();
     }
 
-    let root_canon =
-        fs::canonicalize(&source.root).map_err(|err| format!("Failed to resolve root: {}", err))?;
-    let skill_canon =
-        fs::canonicalize(&skill_dir).map_err(|err| format!("Failed to resolve skill: {}", err))?;
+    #[test]
+    fn antigravity_url_and_server_url_convert_between_kinds() {
+        let config = serde_json::json!({
+            "serverUrl": "https://mcp.example.com/mcp",
+            "url": "https://mcp.example.com/legacy",
+            "headers": { "X-Team": "core" }
+        });
+        let standard = antigravity_to_standard_config(&config);
+        assert_eq!(standard["url"], "https://mcp.example.com/mcp");
+        assert_eq!(
+            standard[ANTIGRAVITY_EXTRA_URL_KEY],
+            "https://mcp.example.com/legacy"
+        );
+
+        let round_trip = mcp_config_for_kind(&McpKind::AntigravityJson, &standard).unwrap();
+        assert_eq!(round_trip, config);
+
+        for kind in [
+            McpKind::ClaudeJson,
+            McpKind::CodexToml,
+            McpKind::OpenCodeJson,
+        ] {
+            let converted = mcp_config_for_kind(&kind, &standard).unwrap();
+            assert!(converted.get(ANTIGRAVITY_EXTRA_URL_KEY).is_none());
+            assert_eq!(converted["url"], "https://mcp.example.com/mcp");
+        }
 
-    if !skill_canon.starts_with(&root_canon) {
-        return Err("Refusing to delete outside agent root".to_string());
+        let same = serde_json::json!({
+            "serverUrl": "https://mcp.example.com/mcp",
+            "url": "https://mcp.example.com/mcp"
+        });
+        let standard = antigravity_to_standard_config(&same);
+        assert_eq!(
+            standard,
+            serde_json::json!({ "url": "https://mcp.example.com/mcp" })
+        );
     }
 
-    fs::remove_dir_all(&skill_dir).map_err(|err| format!("Failed to delete skill: {}", err))?;
-    Ok(())
-}
+    #[test]
+    fn skills_with_the_same_name_are_ordered_by_id() {
+        let root = scratch_dir("same-name");
+        let source = test_source(&root, false);
+        for id in ["pdf-b", "Zeta", "pdf-a", "pdf-c"] {
+            write_skill(&root.join(id), if id == "Zeta" { "alpha" } else { "PDF" });
+        }
 
-#[tauri::command]
-fn sync_skills_from_agent(payload: SyncAgentsInput) -> Result<SyncResult, String> {
-    if payload.source_id == payload.target_id {
-        return Err("Source and target must be different".to_string());
+        for _ in 0..3 {
+            let ids: Vec<String> = read_skills(&source)
+                .into_iter()
+                .map(|skill| skill.id)
+                .collect();
+            assert_eq!(ids, vec!["Zeta", "pdf-a", "pdf-b", "pdf-c"]);
+        }
+        fs::remove_dir_all(&root).unwrap();
     }
-    let home = resolve_home()?;
-    let sources = source_configs(&home);
-    let source = sources
-        .iter()
-        .find(|source| source.id == payload.source_id)
-        .ok_or_else(|| "Unknown skill source".to_string())?;
-    let target = sources
-        .iter()
-        .find(|source| source.id == payload.target_id)
-        .ok_or_else(|| "Unknown target source".to_string())?;
 
-    if !source.root.is_dir() {
-        return Err("Source skills directory missing".to_string());
+    #[test]
+    fn array_shaped_mcp_servers_are_read_and_updated_in_place() {
+        let dir = scratch_dir("mcp-array");
+        let path = dir.join("mcp.json");
+        fs::write(
+            &path,
+            r#"{"mcpServers":[
+                {"name":"github","command":"gh-mcp"},
+                {"id":"fetch","url":"https://fetch.example.com/mcp"},
+                {"command":"anonymous"}
+            ]}"#,
+        )
+        .unwrap();
+        let config = custom_mcp_config(&path.display().to_string(), "claudeJson").unwrap();
+
+        let servers = read_mcp_servers(&config, &path).unwrap();
+        let ids: Vec<&str> = servers.iter().map(|server| server.id.as_str()).collect();
+        assert_eq!(ids, vec!["fetch", "github"]);
+        assert_eq!(
+            servers[1].config,
+            serde_json::json!({ "command": "gh-mcp" })
+        );
+        assert_eq!(
+            servers[0].config,
+            serde_json::json!({ "url": "https://fetch.example.com/mcp" })
+        );
+
+        upsert_mcp_servers(
+            &config,
+            HashMap::from([
+                (
+                    "fetch".to_string(),
+                    serde_json::json!({ "url": "https://fetch.example.com/v2" }),
+                ),
+                (
+                    "docs".to_string(),
+                    serde_json::json!({ "command": "docs-mcp" }),
+                ),
+            ]),
+        )
+        .unwrap();
+        let saved = load_json_value(&path).unwrap();
+        let items = saved["mcpServers"].as_array().unwrap();
+        assert_eq!(items.len(), 4);
+        assert_eq!(
+            items[1],
+            serde_json::json!({ "id": "fetch", "url": "https://fetch.example.com/v2" })
+        );
+        assert_eq!(
+            items[3],
+            serde_json::json!({ "name": "docs", "command": "docs-mcp" })
+        );
+        fs::remove_dir_all(&dir).unwrap();
     }
-    fs::create_dir_all(&target.root)
-        .map_err(|err| format!("Failed to create {}: {}", target.root.display(), err))?;
 
-    let source_root = fs::canonicalize(&source.root)
-        .map_err(|err| format!("Failed to resolve source root: {}", err))?;
-    let skills = read_skills(source);
-    let mut added = 0;
-    let mut skipped = 0;
+    #[test]
+    fn core_file_lookup_ignores_case_but_prefers_exact_match() {
+        let dir = scratch_dir("core-case");
+        fs::write(dir.join("skill.MD"), "# lower").unwrap();
+        let (path, name) = find_core_file(&dir, &["SKILL.md"]).unwrap();
+        assert_eq!(name, "skill.MD");
+        assert_eq!(path, dir.join("skill.MD"));
+
+        fs::write(dir.join("Skill.md"), "# mixed").unwrap();
+        fs::write(dir.join("SKILL.md"), "# exact").unwrap();
+        assert_eq!(find_core_file(&dir, &["SKILL.md"]).unwrap().1, "SKILL.md");
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
-    for skill in skills {
-        let skill_dir = source.root.join(&skill.id);
-        let skill_canon = fs::canonicalize(&skill_dir)
-            .map_err(|err| format!("Failed to resolve skill: {}", err))?;
-        if !skill_canon.starts_with(&source_root) {
-            return Err("Refusing to copy outside agent root".to_string());
-        }
+    #[test]
+    fn nested_core_file_is_found_but_collections_are_not_skills() {
+        let dir = scratch_dir("nested-core");
+        write_skill(&dir.join("pdf").join("src"), "pdf");
+        fs::write(dir.join("pdf").join("README.md"), "# readme").unwrap();
+        let (path, name) = find_core_file(&dir.join("pdf"), &["SKILL.md"]).unwrap();
+        assert_eq!(name, "src/SKILL.md");
+        assert_eq!(path, dir.join("pdf").join("src").join("SKILL.md"));
+
+        write_skill(&dir.join("tools").join("docx"), "docx");
+        write_skill(&dir.join("tools").join("xlsx"), "xlsx");
+        assert!(find_core_file(&dir.join("tools"), &["SKILL.md"]).is_none());
+
+        write_skill(&dir.join("deep").join("a").join("b"), "deep");
+        assert!(find_core_file(&dir.join("deep"), &["SKILL.md"]).is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
-        let target_dir = target.root.join(&skill.id);
-        if target_dir.exists() {
-            skipped += 1;
-            continue;
-        }
-        copy_dir_recursive(&skill_dir, &target_dir)?;
-        added += 1;
+    #[test]
+    fn remote_core_file_is_written_where_the_tree_keeps_it() {
+        let dir = scratch_dir("remote-core");
+        let skill_dir = dir.join("pdf");
+        let remote = RemoteCore {
+            content: "---\nname: pdf\n---\nfresh\n".to_string(),
+            core_file_name: "SKILL.md".to_string(),
+            github: None,
+            archive: Some(SkillArchive {
+                bytes: zip_bytes(&[
+                    ("pdf-main/README.md", b"# readme"),
+                    ("pdf-main/src/SKILL.md", b"stale"),
+                ]),
+                prefix: "pdf-main/".to_string(),
+            }),
+        };
+
+        write_remote_skill(remote, "https://example.com/pdf.zip", &skill_dir).unwrap();
+        let (core_path, core_name) = find_core_file(&skill_dir, &["SKILL.md"]).unwrap();
+        assert_eq!(core_name, "src/SKILL.md");
+        assert_eq!(
+            fs::read_to_string(core_path).unwrap(),
+            "---\nname: pdf\n---\nfresh\n"
+        );
+        assert!(!skill_dir.join("SKILL.md").exists());
+        fs::remove_dir_all(&dir).unwrap();
     }
 
-    Ok(SyncResult { added, skipped })
-}
+    #[test]
+    fn sse_server_with_message_url_survives_format_round_trip() {
+        let dir = scratch_dir("sse-round-trip");
+        let claude_path = dir.join("claude.json");
+        fs::write(
+            &claude_path,
+            r#"{"mcpServers":{"events":{
+                "type":"sse",
+                "url":"https://mcp.example.com/sse",
+                "postUrl":"https://mcp.example.com/messages",
+                "headers":{"Authorization":"Bearer ${input:token}"}
+            }}}"#,
+        )
+        .unwrap();
+        let codex_path = dir.join("config.toml");
+        fs::write(&codex_path, "").unwrap();
+        let opencode_path = dir.join("opencode.json");
+        fs::write(&opencode_path, "{}").unwrap();
+
+        let claude = custom_mcp_config(&claude_path.display().to_string(), "claudeJson").unwrap();
+        let codex = custom_mcp_config(&codex_path.display().to_string(), "codexToml").unwrap();
+        let opencode =
+            custom_mcp_config(&opencode_path.display().to_string(), "openCodeJson").unwrap();
+
+        let mut current = read_mcp_servers(&claude, &claude_path).unwrap();
+        for (config, path) in [(&codex, &codex_path), (&opencode, &opencode_path)] {
+            let servers = current
+                .into_iter()
+                .map(|server| (server.id, server.config))
+                .collect();
+            upsert_mcp_servers(config, servers).unwrap();
+            current = read_mcp_servers(config, path).unwrap();
+
+            assert_eq!(current.len(), 1);
+            let server = &current[0].config;
+            assert_eq!(mcp_server_transport(server), Some("sse"));
+            assert_eq!(server["url"], "https://mcp.example.com/sse");
+            assert_eq!(
+                server[SSE_MESSAGE_URL_KEY],
+                "https://mcp.example.com/messages"
+            );
+            assert_eq!(server["headers"]["Authorization"], "Bearer ${input:token}");
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
-#[tauri::command]
-fn list_mcp_sources() -> Result<Vec<McpSource>, String> {
-    let home = resolve_home()?;
-    let configs = mcp_source_configs(&home);
-    let mut response = Vec::new();
+    #[test]
+    fn commented_frontmatter_lines_are_ignored() {
+        let raw = "---\n# name: old\nname: pdf\n  # description: hidden\n---\nBody\n";
+        let (metadata, body) = parse_frontmatter(raw);
+        assert_eq!(metadata.get("name").map(String::as_str), Some("pdf"));
+        assert!(!metadata.contains_key("# name"));
+        assert!(!metadata.contains_key("description"));
+        assert_eq!(body, "Body");
+
+        let updated = set_frontmatter_value(raw, "name", Some("pdf-tools"));
+        assert_eq!(
+            updated,
+            "---\n# name: old\nname: pdf-tools\n  # description: hidden\n---\nBody\n"
+        );
+        let commented_only = "---\n# version: 1\n---\nBody\n";
+        assert_eq!(
+            set_frontmatter_value(commented_only, "version", Some("2")),
+            "---\n# version: 1\nversion: 2\n---\nBody\n"
+        );
+        assert_eq!(
+            set_frontmatter_value(commented_only, "version", None),
+            commented_only
+        );
+    }
 
-    for config in configs {
-        let has_config =
-            config.read_paths.iter().any(|path| path.exists()) || config.primary_path.exists();
-        if !config.install_root.is_dir() && !has_config {
-            continue;
+    #[test]
+    fn default_branch_is_read_from_github_and_cached_until_expiry() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let served = std::sync::Arc::new(AtomicUsize::new(0));
+        let counter = served.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut byte = [0u8; 1];
+                while !request.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+                    request.push(byte[0]);
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                let body = r#"{"default_branch":"trunk"}"#;
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+            }
+        });
+        let api_base = format!("http://{}", address);
+
+        let agent = http_agent();
+        assert_eq!(
+            fetch_github_default_branch_from(&agent, &api_base, "acme", "trunk-skills").unwrap(),
+            "trunk"
+        );
+        assert_eq!(
+            fetch_github_default_branch_from(&agent, &api_base, "acme", "trunk-skills").unwrap(),
+            "trunk"
+        );
+        assert_eq!(served.load(Ordering::SeqCst), 1);
+
+        if let Some(entry) = DEFAULT_BRANCHES
+            .get()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .get_mut("acme/trunk-skills")
+        {
+            entry.0 -= DEFAULT_BRANCH_CACHE_SECS;
         }
-        let path = resolve_read_path(&config);
-        let exists = path.exists();
-        let servers = if exists {
-            read_mcp_servers(&config, &path)?
-        } else {
-            vec![]
+        assert_eq!(
+            fetch_github_default_branch_from(&agent, &api_base, "acme", "trunk-skills").unwrap(),
+            "trunk"
+        );
+        assert_eq!(served.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn three_level_monorepo_path_resolves_to_the_skill_directory() {
+        let url = "https://github.com/acme/monorepo/tree/main/packages/skills/foo";
+        let location = parse_github_location(url).unwrap();
+        assert_eq!(location.owner, "acme");
+        assert_eq!(location.repo, "monorepo");
+        assert_eq!(location.branch.as_deref(), Some("main"));
+        assert_eq!(location.path, "packages/skills/foo");
+        assert_eq!(
+            github_skill_dir_path(&location, "SKILL.md"),
+            "packages/skills/foo"
+        );
+        assert_eq!(
+            github_file_path(&location, "SKILL.md"),
+            "packages/skills/foo/SKILL.md"
+        );
+        assert_eq!(
+            parse_skill_urls(url, "SKILL.md").unwrap()[0],
+            "https://raw.githubusercontent.com/acme/monorepo/main/packages/skills/foo/SKILL.md"
+        );
+
+        let remote = RemoteCore {
+            content: "---\nname: Foo Helper\n---\n".to_string(),
+            core_file_name: "SKILL.md".to_string(),
+            github: Some((location, vec!["main".to_string()])),
+            archive: None,
         };
+        assert_eq!(slugify(&remote_skill_name(url, &remote)), "foo-helper");
+    }
 
-        response.push(McpSource {
-            id: config.id.to_string(),
-            label: config.label.to_string(),
-            path: path.display().to_string(),
-            format: config.format.to_string(),
-            exists,
-            servers,
-        });
+    #[test]
+    fn copy_dir_newer_copies_only_newer_and_missing_files() {
+        let dir = scratch_dir("copy-newer");
+        let (src, dest) = (dir.join("src"), dir.join("dest"));
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&dest).unwrap();
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs(3600);
+        let write = |path: PathBuf, content: &str, modified: SystemTime| {
+            fs::write(&path, content).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        };
+        write(src.join("newer.txt"), "src newer", now);
+        write(dest.join("newer.txt"), "dest newer", old);
+        write(src.join("older.txt"), "src older", old);
+        write(dest.join("older.txt"), "dest older", now);
+        write(src.join("absent.txt"), "src absent", old);
+        write(dest.join("extra.txt"), "dest extra", old);
+
+        let ignore = SkillIgnore::load(&src);
+        let mut stats = CopyStats::default();
+        copy_dir_newer(&src, &dest, "", &ignore, false, &mut stats).unwrap();
+        let read = |name: &str| fs::read_to_string(dest.join(name)).unwrap();
+        assert_eq!(read("newer.txt"), "src newer");
+        assert_eq!(read("older.txt"), "dest older");
+        assert_eq!(read("absent.txt"), "src absent");
+        assert_eq!(read("extra.txt"), "dest extra");
+        assert_eq!((stats.copied, stats.removed), (2, 0));
+
+        let mut stats = CopyStats::default();
+        copy_dir_newer(&src, &dest, "", &ignore, true, &mut stats).unwrap();
+        assert!(!dest.join("extra.txt").exists());
+        assert_eq!(read("older.txt"), "dest older");
+        assert_eq!(stats.removed, 1);
+        fs::remove_dir_all(&dir).unwrap();
     }
 
-    Ok(response)
-}
+    #[test]
+    fn bom_prefixed_json_and_toml_configs_load() {
+        let dir = scratch_dir("bom");
+        assert_eq!(strip_bom("\u{feff}{}"), "{}");
+        assert_eq!(strip_bom("{}"), "{}");
 
-#[tauri::command]
-fn sync_mcp_from_agent(payload: SyncAgentsInput) -> Result<SyncResult, String> {
-    if payload.source_id == payload.target_id {
-        return Err("Source and target must be different".to_string());
+        let json_path = dir.join("mcp.json");
+        fs::write(
+            &json_path,
+            "\u{feff}{\"mcpServers\":{\"fs\":{\"command\":\"fs-mcp\"}}}",
+        )
+        .unwrap();
+        let json = load_json_value(&json_path).unwrap();
+        assert_eq!(json["mcpServers"]["fs"]["command"], "fs-mcp");
+
+        let toml_path = dir.join("config.toml");
+        fs::write(
+            &toml_path,
+            "\u{feff}[mcp_servers.fs]\ncommand = \"fs-mcp\"\n",
+        )
+        .unwrap();
+        let toml = load_toml_value(&toml_path).unwrap();
+        assert_eq!(
+            toml["mcp_servers"]["fs"]["command"].as_str(),
+            Some("fs-mcp")
+        );
+
+        fs::write(&json_path, "\u{feff}  \n").unwrap();
+        assert_eq!(load_json_value(&json_path).unwrap(), serde_json::json!({}));
+        fs::remove_dir_all(&dir).unwrap();
     }
-    let home = resolve_home()?;
-    let configs = mcp_source_configs(&home);
-    let source = configs
-        .iter()
-        .find(|config| config.id == payload.source_id)
-        .ok_or_else(|| "Unknown MCP source".to_string())?;
-    let target = configs
-        .iter()
-        .find(|config| config.id == payload.target_id)
-        .ok_or_else(|| "Unknown MCP target".to_string())?;
 
-    let source_path = resolve_read_path(source);
-    let source_servers = read_mcp_servers(source, &source_path)?;
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_core_file_must_stay_within_the_skills_root() {
+        let dir = scratch_dir("core-link");
+        let root = dir.join("skills");
+        let source = test_source(&root, false);
+        write_skill(&root.join("shared"), "shared");
+        fs::write(dir.join("outside.md"), "---\nname: outside\n---\n").unwrap();
+
+        let inside = root.join("inside");
+        fs::create_dir_all(&inside).unwrap();
+        std::os::unix::fs::symlink(
+            root.join("shared").join("SKILL.md"),
+            inside.join("SKILL.md"),
+        )
+        .unwrap();
+        let escaped = root.join("escaped");
+        fs::create_dir_all(&escaped).unwrap();
+        std::os::unix::fs::symlink(dir.join("outside.md"), escaped.join("SKILL.md")).unwrap();
+        let broken = root.join("broken");
+        fs::create_dir_all(&broken).unwrap();
+        std::os::unix::fs::symlink(dir.join("missing.md"), broken.join("SKILL.md")).unwrap();
+
+        let core = |skill: &Path| skill.join("SKILL.md");
+        let skill = load_skill(&inside, &core(&inside), "SKILL.md", &source).unwrap();
+        assert_eq!(skill.name, "shared");
+        assert!(ensure_core_link_within_root(&core(&escaped), &source)
+            .unwrap_err()
+            .contains("links outside the skills root"));
+        assert!(load_skill(&escaped, &core(&escaped), "SKILL.md", &source).is_err());
+        assert!(ensure_core_link_within_root(&core(&broken), &source)
+            .unwrap_err()
+            .starts_with("Broken core file link"));
+
+        let ids: Vec<String> = read_skills(&source)
+            .into_iter()
+            .map(|skill| skill.id)
+            .collect();
+        assert_eq!(ids, vec!["inside", "shared"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
-    let target_path = resolve_read_path(target);
-    let target_servers = if target_path.exists() {
-        read_mcp_servers(target, &target_path)?
-    } else {
-        Vec::new()
-    };
-    let existing_ids: HashSet<String> =
-        target_servers.into_iter().map(|server| server.id).collect();
+    #[test]
+    fn opencode_servers_key_follows_the_existing_layout() {
+        let key = |value: JsonValue| opencode_servers_key(&value);
+        assert_eq!(key(serde_json::json!({})), "mcp");
+        assert_eq!(key(serde_json::json!({ "mcp": {} })), "mcp");
+        assert_eq!(key(serde_json::json!({ "mcp": { "a": {} } })), "mcp");
+        assert_eq!(key(serde_json::json!({ "mcpServers": {} })), "mcpServers");
+        assert_eq!(
+            key(serde_json::json!({ "mcpServers": { "a": {} } })),
+            "mcpServers"
+        );
+        assert_eq!(
+            key(serde_json::json!({ "mcp": {}, "mcpServers": { "a": {} } })),
+            "mcpServers"
+        );
+        assert_eq!(
+            key(serde_json::json!({ "mcp": { "a": {} }, "mcpServers": { "b": {} } })),
+            "mcp"
+        );
+        assert_eq!(
+            key(serde_json::json!({ "mcp": {}, "mcpServers": {} })),
+            "mcp"
+        );
+
+        let dir = scratch_dir("opencode-key");
+        let path = dir.join("opencode.json");
+        fs::write(
+            &path,
+            r#"{"mcpServers":{"fs":{"type":"local","command":["fs-mcp"]}}}"#,
+        )
+        .unwrap();
+        let config = custom_mcp_config(&path.display().to_string(), "openCodeJson").unwrap();
+        upsert_mcp_servers(
+            &config,
+            HashMap::from([(
+                "web".to_string(),
+                serde_json::json!({ "url": "https://web.example.com" }),
+            )]),
+        )
+        .unwrap();
+        let saved = load_json_value(&path).unwrap();
+        assert!(saved.get("mcp").is_none());
+        assert_eq!(saved["mcpServers"]["web"]["type"], "remote");
+        let ids: Vec<String> = read_mcp_servers(&config, &path)
+            .unwrap()
+            .into_iter()
+            .map(|server| server.id)
+            .collect();
+        assert_eq!(ids, vec!["fs", "web"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
-    let mut added = 0;
-    let mut skipped = 0;
-    let mut to_insert = HashMap::new();
+    #[cfg(unix)]
+    #[test]
+    fn skill_tree_stops_at_looping_links() {
+        let dir = scratch_dir("tree-loop");
+        let skill = dir.join("loop");
+        write_skill(&skill.join("docs"), "loop");
+        std::os::unix::fs::symlink(&skill, skill.join("docs").join("back")).unwrap();
+        let outside = dir.join("outside");
+        write_skill(&outside, "outside");
+        std::os::unix::fs::symlink(&outside, skill.join("escape")).unwrap();
+        std::os::unix::fs::symlink(skill.join("docs"), skill.join("docs-link")).unwrap();
+
+        let tree = build_skill_tree(&skill).unwrap();
+        let child = |name: &str| {
+            tree.children
+                .iter()
+                .find(|child| child.name == name)
+                .unwrap()
+        };
+        let escape = child("escape");
+        assert_eq!(escape.kind, "link");
+        assert!(escape.truncated);
+        assert!(escape.children.is_empty());
+        let docs_link = child("docs-link");
+        assert!(!docs_link.truncated);
+        assert!(docs_link
+            .children
+            .iter()
+            .any(|child| child.name == "SKILL.md"));
+        let docs = tree
+            .children
+            .iter()
+            .find(|child| child.name == "docs")
+            .unwrap();
+        let back = docs
+            .children
+            .iter()
+            .find(|child| child.name == "back")
+            .unwrap();
+        assert_eq!(back.kind, "link");
+        assert!(back.truncated);
+        assert!(back.children.is_empty());
+        assert!(!tree.truncated && !docs.truncated);
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
-    for server in source_servers {
-        if existing_ids.contains(&server.id) {
-            skipped += 1;
-            continue;
-        }
-        to_insert.insert(server.id.clone(), server.config);
-        added += 1;
+    #[test]
+    fn opencode_env_and_environment_merge_with_environment_winning() {
+        let env = serde_json::json!({ "SHARED": "from-env", "ONLY_ENV": "1" });
+        let environment =
+            serde_json::json!({ "SHARED": "from-environment", "ONLY_ENVIRONMENT": "2" });
+        assert_eq!(
+            merge_opencode_env(Some(&env), Some(&environment)),
+            Some(serde_json::json!({
+                "SHARED": "from-environment",
+                "ONLY_ENV": "1",
+                "ONLY_ENVIRONMENT": "2"
+            }))
+        );
+
+        let disjoint = serde_json::json!({ "OTHER": "3" });
+        assert_eq!(
+            merge_opencode_env(Some(&env), Some(&disjoint)),
+            Some(serde_json::json!({ "SHARED": "from-env", "ONLY_ENV": "1", "OTHER": "3" }))
+        );
+        assert_eq!(merge_opencode_env(Some(&env), None), Some(env.clone()));
+        assert_eq!(
+            merge_opencode_env(None, Some(&disjoint)),
+            Some(disjoint.clone())
+        );
+        assert_eq!(merge_opencode_env(None, None), None);
+
+        let config = serde_json::json!({
+            "type": "local",
+            "command": ["api-server"],
+            "env": env,
+            "environment": environment
+        });
+        let standard = opencode_to_standard_config(&config);
+        assert!(standard.get("environment").is_none());
+        assert_eq!(standard["env"]["SHARED"], "from-environment");
+        assert_eq!(standard["env"]["ONLY_ENV"], "1");
     }
 
-    if !to_insert.is_empty() {
-        upsert_mcp_servers(target, to_insert)?;
+    #[test]
+    fn upstream_sync_prunes_removed_files_but_keeps_ignored_ones() {
+        let dir = scratch_dir("upstream-prune");
+        let (upstream, skill) = (dir.join("upstream"), dir.join("skill"));
+        write_skill(&upstream, "pdf");
+        fs::create_dir_all(upstream.join("scripts")).unwrap();
+        fs::write(upstream.join("scripts").join("run.sh"), "new").unwrap();
+        fs::write(upstream.join("notes.md"), "upstream notes").unwrap();
+
+        write_skill(&skill, "pdf");
+        fs::create_dir_all(skill.join("scripts")).unwrap();
+        fs::create_dir_all(skill.join("old")).unwrap();
+        fs::write(skill.join("scripts").join("run.sh"), "old").unwrap();
+        fs::write(skill.join("scripts").join("removed.sh"), "gone").unwrap();
+        fs::write(skill.join("old").join("file.txt"), "gone").unwrap();
+        fs::write(skill.join("scratch.log"), "local").unwrap();
+        fs::write(skill.join("notes.md"), "local notes").unwrap();
+        fs::write(skill.join(SKILL_SOURCE_FILENAME), "{}").unwrap();
+        fs::write(skill.join(SKILL_IGNORE_FILENAME), "*.log\nnotes.md\n").unwrap();
+
+        apply_upstream_tree(&upstream, &skill).unwrap();
+        let read = |relative: &str| fs::read_to_string(skill.join(relative)).ok();
+        assert_eq!(read("scripts/run.sh").as_deref(), Some("new"));
+        assert_eq!(read("scripts/removed.sh"), None);
+        assert!(!skill.join("old").exists());
+        assert_eq!(read("scratch.log").as_deref(), Some("local"));
+        assert_eq!(read("notes.md").as_deref(), Some("local notes"));
+        assert!(skill.join(SKILL_SOURCE_FILENAME).exists());
+        assert!(skill.join(SKILL_IGNORE_FILENAME).exists());
+        fs::remove_dir_all(&dir).unwrap();
     }
 
-    Ok(SyncResult { added, skipped })
-}
+    #[test]
+    fn hidden_staging_dirs_are_not_listed_as_skills() {
+        let root = scratch_dir("hidden-staging");
+        let source = test_source(&root, false);
+        write_skill(&root.join("pdf"), "pdf");
+        write_skill(&root.join(".pdf.reset-1-2"), "pdf");
+        write_skill(&root.join(".pdf.previous-1-2"), "pdf");
 
-#[tauri::command]
-fn upsert_mcp_server_json(payload: UpsertMcpJsonInput) -> Result<(), String> {
-    let home = resolve_home()?;
-    let configs = mcp_source_configs(&home);
-    let config = configs
-        .iter()
-        .find(|config| config.id == payload.source_id)
-        .ok_or_else(|| "Unknown MCP source".to_string())?;
+        let ids: Vec<String> = read_skills(&source)
+            .into_iter()
+            .map(|skill| skill.id)
+            .collect();
+        assert_eq!(ids, vec!["pdf"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
 
-    let servers = parse_mcp_json(&payload.json)?;
-    upsert_mcp_servers(config, servers)
-}
+    #[test]
+    fn skill_icons_are_cached_until_the_file_changes() {
+        let dir = scratch_dir("icon-cache");
+        let path = dir.join("icon.svg");
+        let stamp = SystemTime::now() - Duration::from_secs(60);
+        let write = |content: &str, modified: SystemTime| {
+            fs::write(&path, content).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        };
+        let decode = |icon: SkillIcon| {
+            base64::engine::general_purpose::STANDARD
+                .decode(icon.data)
+                .unwrap()
+        };
 
-#[tauri::command]
-fn delete_mcp_server(payload: DeleteMcpInput) -> Result<(), String> {
-    let home = resolve_home()?;
-    let configs = mcp_source_configs(&home);
-    let config = configs
-        .iter()
-        .find(|config| config.id == payload.source_id)
-        .ok_or_else(|| "Unknown MCP source".to_string())?;
+        write("<svg>a</svg>", stamp);
+        assert_eq!(
+            decode(read_skill_icon(&path, "image/svg+xml").unwrap()),
+            b"<svg>a</svg>"
+        );
+        write("<svg>b</svg>", stamp);
+        assert_eq!(
+            decode(read_skill_icon(&path, "image/svg+xml").unwrap()),
+            b"<svg>a</svg>"
+        );
+        write("<svg>b</svg>", stamp + Duration::from_secs(1));
+        assert_eq!(
+            decode(read_skill_icon(&path, "image/svg+xml").unwrap()),
+            b"<svg>b</svg>"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
-    delete_mcp_server_for_source(config, &payload.id)
-}
+    #[test]
+    fn history_errors_do_not_keep_url_secrets() {
+        assert_eq!(
+            history_error(
+                "Failed to download https://user:pw@cdn.example.com/pdf.zip?sig=abc: status 403"
+            ),
+            "Failed to download https://cdn.example.com/pdf.zip: status 403"
+        );
+        assert_eq!(
+            history_error("Request failed: http://example.com/a?token=1 (timeout), https://b.example.com/#frag."),
+            "Request failed: http://example.com/a (timeout), https://b.example.com/."
+        );
+        assert_eq!(history_error("Missing core file"), "Missing core file");
+        assert_eq!(history_url("https://[bad?token=1"), "https://[bad");
+    }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![
-            list_skills,
-            list_skill_tree,
-            install_skill_from_url,
-            sync_skill_from_url,
-            delete_skill,
-            sync_skills_from_agent,
-            list_mcp_sources,
-            sync_mcp_from_agent,
-            upsert_mcp_server_json,
-            delete_mcp_server
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+    #[test]
+    fn install_slug_preview_reports_the_skill_that_will_be_updated() {
+        let root = scratch_dir("install-preview");
+        let source = test_source(&root, false);
+        let url = "https://github.com/acme/skills/tree/main/pdf";
+        write_skill(&root.join("pdf"), "pdf");
+        write_skill_source(
+            &root.join("pdf"),
+            &SkillSourceRecord {
+                url: url.to_string(),
+                branch: None,
+                core_hash: None,
+            },
+        )
+        .unwrap();
+
+        for on_existing in [None, Some("update")] {
+            let preview = preview_install_slug_for(&source, url, on_existing).unwrap();
+            assert_eq!(preview.slug, "pdf");
+            assert!(preview.updates_existing);
+        }
+        assert!(preview_install_slug_for(&source, url, Some("replace")).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
 }
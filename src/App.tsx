@@ -22,6 +22,7 @@ type SkillSource = {
   id: string;
   label: string;
   root: string;
+  installed: boolean;
   exists: boolean;
   skills: Skill[];
 };
@@ -31,6 +32,7 @@ type SkillTreeNode = {
   path: string;
   kind: "file" | "dir" | "link";
   children: SkillTreeNode[];
+  truncated: boolean;
 };
 
 type McpServer = {
@@ -50,6 +52,10 @@ type McpSource = {
 type SyncResult = {
   added: number;
   skipped: number;
+  updated: number;
+  addedIds: string[];
+  skippedIds: string[];
+  updatedIds: string[];
 };
 
 type ToastTone = "success" | "error" | "info";
@@ -473,7 +479,7 @@ function App() {
     setError(null);
     try {
       const result = await invoke<SkillSource[]>("list_skills");
-      setSources(result);
+      setSources(result.filter((source) => source.installed));
     } catch (err) {
       setError(String(err));
     } finally {